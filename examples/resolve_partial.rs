@@ -1,11 +1,12 @@
+#![allow(clippy::uninlined_format_args)]
+
 use anchor_version_detector::{resolve_versions, ProjectVersions};
 use anyhow::Result;
 
 fn main() -> Result<()> {
     let detected = ProjectVersions {
-        rust_version: None,
-        solana_version: None,
         anchor_version: Some("0.31.0".to_string()),
+        ..ProjectVersions::default()
     };
 
     let (resolved, assessment, warnings) = resolve_versions(&detected)?;
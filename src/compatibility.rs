@@ -1,205 +1,137 @@
+use std::cmp::Ordering;
+
 use anyhow::{Result, anyhow};
+use semver::{BuildMetadata, Op, Version, VersionReq};
 
-use crate::parse::clean_version;
+use crate::parse::{
+    exact_version_requirement, parse_rust_toolchain_spec, parse_semver_range,
+    requirement_lower_bound, requirement_upper_bound, requirements_overlap,
+};
+use crate::rust_releases::stable_equivalent;
 use crate::types::{
-    CompatibilityAssessment, CompatibilityReason, CompatibilityRule, Confidence, InferredFields,
-    ProjectVersions,
+    CandidateMatch, CompatibilityAssessment, CompatibilityMatrix, CompatibilityReason,
+    CompatibilityRule, Confidence, FallbackPolicy, Inconsistency, InferredFields, MatchKind,
+    PlatformToolsRelease, ProjectVersions, RuleMatch, RustVersionMapping, ToolchainChannel,
+    TsClientVersions, VersionDistance, VersionField,
 };
 
-const COMPATIBILITY_RULES: [CompatibilityRule; 17] = [
-    // Anchor > v1
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.1.2",
-        rust: "1.89.0",
-        notes: "Anchor 1.1.2 toolchain example lists Solana 3.1.10; Rust template MSRV remains 1.89.0.",
-        source: "https://github.com/otter-sec/anchor/blob/v1.1.2/docs/content/docs/references/anchor-toml.mdx",
-    },
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.1.1",
-        rust: "1.89.0",
-        notes: "Anchor 1.1.1 toolchain example lists Solana 3.1.10; Rust template MSRV remains 1.89.0.",
-        source: "https://github.com/otter-sec/anchor/blob/v1.1.1/docs/content/docs/references/anchor-toml.mdx",
-    },
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.1.0",
-        rust: "1.89.0",
-        notes: "Anchor 1.1.0 toolchain example lists Solana 3.1.10; Rust template MSRV remains 1.89.0.",
-        source: "https://github.com/solana-foundation/anchor/blob/v1.1.0/docs/content/docs/references/anchor-toml.mdx",
-    },
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.0.3",
-        rust: "1.89.0",
-        notes: "Patch release aligned with Anchor 1.0.x Solana 3.1.10 compatibility and Rust template MSRV 1.89.0.",
-        source: "https://github.com/otter-sec/anchor/blob/v1.0.3/docs/content/docs/updates/release-notes/1-0-3.mdx",
-    },
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.0.2",
-        rust: "1.89.0",
-        notes: "Anchor 1.0.0 compatibility from the v1.0.0 release notes and Rust template.",
-        source: "https://www.anchor-lang.com/docs/updates/release-notes/1-0-0",
-    },
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.0.1",
-        rust: "1.89.0",
-        notes: "Anchor 1.0.0 compatibility from the v1.0.0 release notes and Rust template.",
-        source: "https://www.anchor-lang.com/docs/updates/release-notes/1-0-0",
-    },
-    CompatibilityRule {
-        solana: "3.1.10",
-        anchor: "1.0.0",
-        rust: "1.89.0",
-        notes: "Anchor 1.0.0 compatibility from the v1.0.0 release notes and Rust template.",
-        source: "https://www.anchor-lang.com/docs/updates/release-notes/1-0-0",
-    },
-    // Anchor < v1
-    CompatibilityRule {
-        solana: "2.3.0",
-        anchor: "0.32.1",
-        rust: "1.89.0",
-        notes: "Anchor 0.32.1 release notes list Solana 2.3.0; Rust MSRV remains 1.89.0 from 0.32.0.",
-        source: "https://github.com/otter-sec/anchor/blob/v0.32.1/docs/content/docs/updates/release-notes/0-32-1.mdx",
-    },
-    CompatibilityRule {
-        solana: "2.3.0",
-        anchor: "0.32.0",
-        rust: "1.89.0",
-        notes: "Anchor 0.32.0 release notes list Solana 2.3.0 and Rust 1.89.0 MSRV.",
-        source: "https://github.com/otter-sec/anchor/blob/v0.32.0/docs/content/docs/updates/release-notes/0-32-0.mdx",
-    },
-    CompatibilityRule {
-        solana: "2.1.0",
-        anchor: "0.31.1",
-        rust: "1.84.1",
-        notes: "Patch release states the recommended Solana version is unchanged from 0.31.0.",
-        source: "https://github.com/otter-sec/anchor/blob/v0.31.1/docs/content/docs/updates/release-notes/0-31-1.mdx",
-    },
-    CompatibilityRule {
-        solana: "2.1.0",
-        anchor: "0.31.0",
-        rust: "1.84.1",
-        notes: "Based on the Agave rust-toolchain and Anchor 0.31.0 release notes.",
-        source: "https://www.anchor-lang.com/release-notes/0.31.0",
-    },
-    CompatibilityRule {
-        solana: "1.18.17",
-        anchor: "0.30.1",
-        rust: "1.76.0",
-        notes: "Based on the Solana rust-toolchain and Anchor 0.30.1 release notes.",
-        source: "https://www.anchor-lang.com/release-notes/0.30.1",
-    },
-    CompatibilityRule {
-        solana: "1.18.8",
-        anchor: "0.30.0",
-        rust: "1.76.0",
-        notes: "Based on the Solana rust-toolchain and Anchor 0.30.0 release notes.",
-        source: "https://www.anchor-lang.com/release-notes/0.30.0",
-    },
-    CompatibilityRule {
-        solana: "1.17.0",
-        anchor: "0.29.0",
-        rust: "1.69.0",
-        notes: "Listed directly in the Anchor 0.29.0 release notes.",
-        source: "https://www.anchor-lang.com/release-notes/0.29.0",
-    },
-    CompatibilityRule {
-        solana: "1.16.0",
-        anchor: "0.28.0",
-        rust: "1.68.0",
-        notes: "Listed in the Anchor changelog for 0.28.0.",
-        source: "https://www.anchor-lang.com/release-notes/changelog#0-28-0-2023-06-09",
-    },
-    CompatibilityRule {
-        solana: "1.15.0",
-        anchor: "0.27.0",
-        rust: "1.67.0",
-        notes: "Historical compatibility entry retained from the original detector matrix.",
-        source: "project compatibility matrix",
-    },
-    CompatibilityRule {
-        solana: "1.14.0",
-        anchor: "0.26.0",
-        rust: "1.66.0",
-        notes: "Historical compatibility entry retained from the original detector matrix.",
-        source: "project compatibility matrix",
-    },
+const fn platform_tools(
+    solana: &'static str,
+    platform_tools: &'static str,
+    rustc: &'static str,
+) -> PlatformToolsRelease {
+    PlatformToolsRelease {
+        solana,
+        platform_tools,
+        rustc,
+        cargo: rustc,
+    }
+}
+
+/// The platform-tools release each Solana/Agave release line installs by
+/// default, newest first. Platform-tools ships rustc and cargo at the same
+/// version, usually a `-dev` build of that release.
+const PLATFORM_TOOLS_RELEASES: [PlatformToolsRelease; 9] = [
+    platform_tools("3.1", "v1.51", "1.89.0"),
+    platform_tools("3.0", "v1.50", "1.84.1"),
+    platform_tools("2.3", "v1.48", "1.84.1"),
+    platform_tools("2.2", "v1.45", "1.79.0"),
+    platform_tools("2.1", "v1.43", "1.79.0"),
+    platform_tools("2.0", "v1.41", "1.75.0"),
+    platform_tools("1.18", "v1.41", "1.75.0"),
+    platform_tools("1.17", "v1.37", "1.68.0"),
+    platform_tools("1.16", "v1.37", "1.68.0"),
 ];
 
 #[must_use]
-pub const fn compatibility_rules() -> &'static [CompatibilityRule] {
-    &COMPATIBILITY_RULES
+pub const fn platform_tools_releases() -> &'static [PlatformToolsRelease] {
+    &PLATFORM_TOOLS_RELEASES
 }
 
+/// The platform-tools release bundled with a Solana version's `major.minor`
+/// release line. Requirements such as `^1.18` use their lower bound.
 #[must_use]
-pub const fn latest_compatible_rule() -> &'static CompatibilityRule {
-    &COMPATIBILITY_RULES[0]
+pub fn platform_tools_for_solana(version: &str) -> Option<&'static PlatformToolsRelease> {
+    let version = parse_semver_range(version);
+    let mut parts = version.split('.');
+    let release_line = format!("{}.{}", parts.next()?, parts.next()?);
+    PLATFORM_TOOLS_RELEASES
+        .iter()
+        .find(|release| release.solana == release_line)
 }
 
+/// Look up a platform-tools release by version, with or without the leading
+/// `v`. When several Solana lines share it, the newest is returned.
 #[must_use]
-pub fn find_rule_by_solana(version: &str) -> Option<&'static CompatibilityRule> {
-    let cleaned = clean_version(version);
-    compatibility_rules()
+pub fn find_platform_tools(version: &str) -> Option<&'static PlatformToolsRelease> {
+    let version = version.trim().trim_start_matches('v');
+    PLATFORM_TOOLS_RELEASES
         .iter()
-        .find(|rule| cleaned.starts_with(rule.solana))
+        .find(|release| release.platform_tools.trim_start_matches('v') == version)
+}
+
+/// The rules of the built-in compatibility matrix.
+#[must_use]
+pub fn compatibility_rules() -> &'static [CompatibilityRule] {
+    &CompatibilityMatrix::built_in().rules
+}
+
+/// The newest rule of the built-in compatibility matrix.
+#[must_use]
+pub fn latest_compatible_rule() -> &'static CompatibilityRule {
+    &CompatibilityMatrix::built_in().rules[0]
+}
+
+#[must_use]
+pub fn find_rule_by_solana(version: &str) -> Option<&'static CompatibilityRule> {
+    CompatibilityMatrix::built_in().find_rule_by_solana(version)
 }
 
 #[must_use]
 pub fn find_rule_by_anchor(version: &str) -> Option<&'static CompatibilityRule> {
-    let cleaned = clean_version(version);
-    compatibility_rules()
-        .iter()
-        .find(|rule| cleaned.starts_with(rule.anchor))
+    CompatibilityMatrix::built_in().find_rule_by_anchor(version)
+}
+
+/// Find the built-in compatibility rule for a Solana version or requirement.
+///
+/// See [`CompatibilityMatrix::match_rule_by_solana`].
+#[must_use]
+pub fn match_rule_by_solana(version: &str) -> Option<RuleMatch<'static>> {
+    CompatibilityMatrix::built_in().match_rule_by_solana(version)
+}
+
+/// Find the built-in compatibility rule for an Anchor version or requirement.
+///
+/// See [`CompatibilityMatrix::match_rule_by_solana`] for the matching
+/// semantics.
+#[must_use]
+pub fn match_rule_by_anchor(version: &str) -> Option<RuleMatch<'static>> {
+    CompatibilityMatrix::built_in().match_rule_by_anchor(version)
+}
+
+/// See [`CompatibilityMatrix::match_rule_by_solana_requirement`].
+#[must_use]
+pub fn match_rule_by_solana_requirement(requirement: &VersionReq) -> Option<RuleMatch<'static>> {
+    CompatibilityMatrix::built_in().match_rule_by_solana_requirement(requirement)
+}
+
+/// See [`CompatibilityMatrix::match_rule_by_anchor_requirement`].
+#[must_use]
+pub fn match_rule_by_anchor_requirement(requirement: &VersionReq) -> Option<RuleMatch<'static>> {
+    CompatibilityMatrix::built_in().match_rule_by_anchor_requirement(requirement)
 }
 
-/// Build compatibility metadata for the detected project versions.
+/// Build compatibility metadata for the detected project versions using the
+/// built-in matrix.
 ///
 /// # Errors
 ///
 /// Returns an error when the input does not look like a Solana or Anchor project.
 pub fn assess_versions(detected: &ProjectVersions) -> Result<CompatibilityAssessment> {
-    if !detected.is_solana_project() {
-        return Err(anyhow!(
-            "This directory does not appear to be a Solana project. No Solana or Anchor version information found.\n\
-            Expected to find one of:\n\
-            - Anchor.toml with toolchain configuration\n\
-            - Cargo.toml with solana-program, anchor-lang, or anchor-spl dependencies"
-        ));
-    }
-
-    if let Some(solana_version) = &detected.solana_version
-        && solana_version != "*"
-        && let Some(rule) = find_rule_by_solana(solana_version)
-    {
-        return Ok(build_assessment(
-            detected,
-            Some(rule),
-            CompatibilityReason::ExactSolanaMatch,
-        ));
-    }
-
-    if let Some(anchor_version) = &detected.anchor_version
-        && let Some(rule) = find_rule_by_anchor(anchor_version)
-    {
-        return Ok(build_assessment(
-            detected,
-            Some(rule),
-            CompatibilityReason::ExactAnchorMatch,
-        ));
-    }
-
-    Ok(build_assessment(
-        detected,
-        None,
-        CompatibilityReason::FallbackLatestKnown,
-    ))
+    CompatibilityMatrix::built_in().assess_versions(detected)
 }
 
-/// Resolve missing versions using the compatibility matrix.
+/// Resolve missing versions using the built-in compatibility matrix.
 ///
 /// # Errors
 ///
@@ -207,71 +139,791 @@ pub fn assess_versions(detected: &ProjectVersions) -> Result<CompatibilityAssess
 pub fn resolve_versions(
     detected: &ProjectVersions,
 ) -> Result<(ProjectVersions, CompatibilityAssessment, Vec<String>)> {
-    let assessment = assess_versions(detected)?;
-    let mut resolved = detected.clone();
-    let mut warnings = Vec::new();
+    CompatibilityMatrix::built_in().resolve_versions(detected)
+}
+
+impl CompatibilityMatrix {
+    /// The newest rule, or `None` for an empty matrix.
+    #[must_use]
+    pub fn latest_rule(&self) -> Option<&CompatibilityRule> {
+        self.rules.first()
+    }
+
+    #[must_use]
+    pub fn find_rule_by_solana(&self, version: &str) -> Option<&CompatibilityRule> {
+        self.match_rule_by_solana(version)
+            .map(|matched| matched.rule)
+    }
+
+    #[must_use]
+    pub fn find_rule_by_anchor(&self, version: &str) -> Option<&CompatibilityRule> {
+        self.match_rule_by_anchor(version)
+            .map(|matched| matched.rule)
+    }
+
+    /// Find the compatibility rule for a Solana version or requirement.
+    ///
+    /// Exact versions match the rule recommending that version, then a rule
+    /// whose tested range contains it, and otherwise inherit from the highest
+    /// earlier patch of the same `major.minor`. Partial versions such as `1.18`
+    /// and requirements such as `>=1.18,<2` match the newest rule whose
+    /// recommended version satisfies them or whose tested range overlaps them.
+    /// Caret requirements such as `^2.1.0` prefer the newest such rule in their
+    /// own `major.minor` release line.
+    #[must_use]
+    pub fn match_rule_by_solana(&self, version: &str) -> Option<RuleMatch<'_>> {
+        self.match_rule(version, VersionField::Solana)
+    }
+
+    /// Find the compatibility rule for an Anchor version or requirement.
+    ///
+    /// See [`CompatibilityMatrix::match_rule_by_solana`] for the matching
+    /// semantics.
+    #[must_use]
+    pub fn match_rule_by_anchor(&self, version: &str) -> Option<RuleMatch<'_>> {
+        self.match_rule(version, VersionField::Anchor)
+    }
+
+    /// Find the newest compatibility rule whose Solana version satisfies
+    /// `requirement`. Exact pins such as `=1.18.26` use the exact-version
+    /// semantics of [`CompatibilityMatrix::match_rule_by_solana`].
+    #[must_use]
+    pub fn match_rule_by_solana_requirement(
+        &self,
+        requirement: &VersionReq,
+    ) -> Option<RuleMatch<'_>> {
+        self.match_rule_query(query_from_requirement(requirement), VersionField::Solana)
+    }
+
+    /// Find the newest compatibility rule whose Anchor version satisfies
+    /// `requirement`. Exact pins such as `=0.30.1` use the exact-version
+    /// semantics of [`CompatibilityMatrix::match_rule_by_anchor`].
+    #[must_use]
+    pub fn match_rule_by_anchor_requirement(
+        &self,
+        requirement: &VersionReq,
+    ) -> Option<RuleMatch<'_>> {
+        self.match_rule_query(query_from_requirement(requirement), VersionField::Anchor)
+    }
+
+    fn match_rule(&self, version: &str, field: VersionField) -> Option<RuleMatch<'_>> {
+        self.match_rule_query(parse_version_query(version)?, field)
+    }
+
+    fn match_rule_query(&self, query: VersionQuery, field: VersionField) -> Option<RuleMatch<'_>> {
+        let rules = self.rules.iter().filter_map(|rule| {
+            Version::parse(rule.version(field))
+                .ok()
+                .map(|parsed| (rule, parsed))
+        });
+
+        match query {
+            VersionQuery::Exact(requested) => {
+                let exact = rules.clone().find(|(_, candidate)| {
+                    requested.pre.is_empty() && same_release(candidate, &requested)
+                });
+                if let Some((rule, _)) = exact {
+                    return Some(RuleMatch {
+                        rule,
+                        kind: MatchKind::Exact,
+                    });
+                }
+
+                let in_range = rules
+                    .clone()
+                    .filter(|(rule, _)| rule.tested.get(field).matches(&requested));
+                let same_release_line = newest(in_range.clone().filter(|(_, candidate)| {
+                    candidate.major == requested.major && candidate.minor == requested.minor
+                }));
+                if let Some(rule) = same_release_line.or_else(|| newest(in_range)) {
+                    return Some(RuleMatch {
+                        rule,
+                        kind: MatchKind::InRange,
+                    });
+                }
+
+                newest(rules.filter(|(_, candidate)| {
+                    candidate.major == requested.major
+                        && candidate.minor == requested.minor
+                        && candidate.patch <= requested.patch
+                }))
+                .map(|rule| RuleMatch {
+                    rule,
+                    kind: MatchKind::PatchInherited,
+                })
+            }
+            VersionQuery::Requirement(requirement) => {
+                let satisfying = rules.filter(|(rule, candidate)| {
+                    requirement.matches(candidate)
+                        || requirements_overlap(&requirement, rule.tested.get(field))
+                });
+                let same_release_line = caret_release_line(&requirement).and_then(|lower| {
+                    newest(satisfying.clone().filter(|(_, candidate)| {
+                        candidate.major == lower.major && candidate.minor == lower.minor
+                    }))
+                });
+                same_release_line
+                    .or_else(|| newest(satisfying))
+                    .map(|rule| RuleMatch {
+                        rule,
+                        kind: MatchKind::Range,
+                    })
+            }
+        }
+    }
+
+    /// Build compatibility metadata for the detected project versions, falling
+    /// back to the newest rule when none matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the input does not look like a Solana or Anchor
+    /// project, or the matrix has no rules.
+    pub fn assess_versions(&self, detected: &ProjectVersions) -> Result<CompatibilityAssessment> {
+        self.assess_versions_with(detected, FallbackPolicy::LatestKnown)
+    }
 
-    if let Some(rule) = assessment.matched_rule {
-        if assessment.inferred_fields.anchor_version {
-            resolved.anchor_version = Some(rule.anchor.to_string());
+    /// Build compatibility metadata for the detected project versions, using
+    /// `policy` when no rule matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the input does not look like a Solana or Anchor
+    /// project, the matrix has no rules, or no rule matches under
+    /// [`FallbackPolicy::Strict`].
+    pub fn assess_versions_with(
+        &self,
+        detected: &ProjectVersions,
+        policy: FallbackPolicy,
+    ) -> Result<CompatibilityAssessment> {
+        if !detected.is_solana_project() {
+            return Err(anyhow!(
+                "This directory does not appear to be a Solana project. No Solana or Anchor version information found.\n\
+                Expected to find one of:\n\
+                - Anchor.toml with toolchain configuration\n\
+                - Cargo.toml or Cargo.lock with recognised Solana or Anchor crates (solana-program, solana-sdk, agave-*, anchor-lang, ...)"
+            ));
         }
-        if assessment.inferred_fields.solana_version {
-            resolved.solana_version = Some(rule.solana.to_string());
+        let latest_rule = self
+            .latest_rule()
+            .ok_or_else(|| anyhow!("Compatibility matrix has no rules"))?;
+
+        let solana_match = detected
+            .solana_version
+            .as_ref()
+            .filter(|version| *version != "*")
+            .and_then(|version| {
+                detected.requirements.solana.as_ref().map_or_else(
+                    || self.match_rule_by_solana(version),
+                    |requirement| self.match_rule_by_solana_requirement(requirement),
+                )
+            })
+            .map(|matched| (matched, CompatibilityReason::ExactSolanaMatch));
+        let anchor_match = detected
+            .requirements
+            .anchor
+            .as_ref()
+            .map_or_else(
+                || {
+                    detected
+                        .anchor_version
+                        .as_ref()
+                        .filter(|version| *version != "*")
+                        .and_then(|version| self.match_rule_by_anchor(version))
+                },
+                |requirement| self.match_rule_by_anchor_requirement(requirement),
+            )
+            .map(|matched| (matched, CompatibilityReason::ExactAnchorMatch));
+        let candidates: Vec<CandidateMatch> = solana_match
+            .into_iter()
+            .chain(anchor_match)
+            .map(|(matched, reason)| CandidateMatch {
+                reason,
+                rule: matched.rule.clone(),
+                match_kind: matched.kind,
+                inconsistencies: find_inconsistencies(detected, matched.rule),
+            })
+            .collect();
+
+        let chosen = candidates
+            .iter()
+            .find(|candidate| candidate.inconsistencies.is_empty())
+            .map(|candidate| (candidate, candidate.reason))
+            .or_else(|| {
+                candidates
+                    .first()
+                    .map(|candidate| (candidate, CompatibilityReason::Conflict))
+            });
+        if let Some((candidate, reason)) = chosen {
+            let matched = RuleMatch {
+                rule: &candidate.rule,
+                kind: candidate.match_kind,
+            };
+            let mut assessment =
+                build_assessment(detected, Some(matched), latest_rule, reason, None);
+            assessment.candidates = candidates;
+            return Ok(assessment);
         }
-        if assessment.inferred_fields.rust_version {
-            resolved.rust_version = Some(rule.rust.to_string());
+
+        if policy == FallbackPolicy::Strict {
+            return Err(anyhow!(
+                "No compatibility rule matches Solana {} or Anchor {}.",
+                detected.solana_version.as_deref().unwrap_or("(unknown)"),
+                detected.anchor_version.as_deref().unwrap_or("(unknown)")
+            ));
+        }
+
+        if let Some((matched, distance)) = self.nearest_rule(detected, policy) {
+            return Ok(build_assessment(
+                detected,
+                Some(matched),
+                latest_rule,
+                CompatibilityReason::FallbackNearestKnown,
+                Some(distance),
+            ));
         }
+
+        Ok(build_assessment(
+            detected,
+            None,
+            latest_rule,
+            CompatibilityReason::FallbackLatestKnown,
+            None,
+        ))
     }
 
-    if resolved
-        .solana_version
-        .as_ref()
-        .is_none_or(|version| version == "*")
+    /// The rule closest to the detected Solana version, or else the detected
+    /// Anchor version, in the direction `policy` asks for. Requirements are
+    /// measured from their lower bound.
+    fn nearest_rule(
+        &self,
+        detected: &ProjectVersions,
+        policy: FallbackPolicy,
+    ) -> Option<(RuleMatch<'_>, VersionDistance)> {
+        [
+            (
+                VersionField::Solana,
+                &detected.solana_version,
+                &detected.requirements.solana,
+            ),
+            (
+                VersionField::Anchor,
+                &detected.anchor_version,
+                &detected.requirements.anchor,
+            ),
+        ]
+        .into_iter()
+        .find_map(|(field, version, requirement)| {
+            let version = version.as_deref().filter(|version| *version != "*")?;
+            let requested = requirement
+                .clone()
+                .or_else(|| exact_version_requirement(version))
+                .as_ref()
+                .and_then(requirement_lower_bound)?;
+            let rules = self.rules.iter().filter_map(|rule| {
+                Version::parse(rule.version(field))
+                    .ok()
+                    .map(|parsed| (rule, parsed))
+            });
+
+            let rule = match policy {
+                FallbackPolicy::NearestLower => {
+                    newest(rules.filter(|(_, candidate)| *candidate < requested))
+                }
+                FallbackPolicy::NearestHigher => rules
+                    .filter(|(_, candidate)| *candidate > requested)
+                    .min_by(|left, right| left.1.cmp(&right.1))
+                    .map(|(rule, _)| rule),
+                FallbackPolicy::LatestKnown | FallbackPolicy::Strict => None,
+            }?;
+            let nearest = Version::parse(rule.version(field)).ok()?;
+
+            Some((
+                RuleMatch {
+                    rule,
+                    kind: MatchKind::Nearest,
+                },
+                VersionDistance {
+                    field,
+                    requested: version.to_string(),
+                    major: nearest.major.abs_diff(requested.major),
+                    minor: nearest.minor.abs_diff(requested.minor),
+                    patch: nearest.patch.abs_diff(requested.patch),
+                },
+            ))
+        })
+    }
+
+    /// Resolve missing versions using this compatibility matrix, falling back
+    /// to the newest rule when none matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the input does not look like a Solana or Anchor
+    /// project, or the matrix has no rules.
+    pub fn resolve_versions(
+        &self,
+        detected: &ProjectVersions,
+    ) -> Result<(ProjectVersions, CompatibilityAssessment, Vec<String>)> {
+        self.resolve_versions_with(detected, FallbackPolicy::LatestKnown)
+    }
+
+    /// Resolve missing versions using this compatibility matrix and `policy`
+    /// when no rule matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the input does not look like a Solana or Anchor
+    /// project, the matrix has no rules, or no rule matches under
+    /// [`FallbackPolicy::Strict`].
+    pub fn resolve_versions_with(
+        &self,
+        detected: &ProjectVersions,
+        policy: FallbackPolicy,
+    ) -> Result<(ProjectVersions, CompatibilityAssessment, Vec<String>)> {
+        let assessment = self.assess_versions_with(detected, policy)?;
+        let mut resolved = detected.clone();
+        let mut warnings = Vec::new();
+
+        if assessment.reason == CompatibilityReason::Conflict {
+            warnings.extend(
+                assessment
+                    .candidates
+                    .iter()
+                    .flat_map(|candidate| &candidate.inconsistencies)
+                    .filter(|inconsistency| inconsistency.field != VersionField::Rust)
+                    .map(|inconsistency| {
+                        format!(
+                            "{:?} {} does not match {:?} {} in the compatibility rule for Anchor {}.",
+                            inconsistency.field,
+                            inconsistency.detected,
+                            inconsistency.field,
+                            inconsistency.expected,
+                            inconsistency.rule.anchor
+                        )
+                    }),
+            );
+        }
+
+        if let (Some(distance), Some(rule)) =
+            (&assessment.fallback_distance, &assessment.matched_rule)
+        {
+            warnings.push(format!(
+                "No compatibility rule covers {:?} {}. Suggesting the nearest known rule, {:?} {}.",
+                distance.field,
+                distance.requested,
+                distance.field,
+                rule.version(distance.field)
+            ));
+        }
+
+        if let Some(rule) = &assessment.matched_rule {
+            if assessment.inferred_fields.anchor_version {
+                resolved.anchor_version = Some(rule.anchor.clone());
+            }
+            if assessment.inferred_fields.solana_version {
+                resolved.solana_version = Some(rule.solana.clone());
+            }
+            if assessment.inferred_fields.rust_version {
+                resolved.rust_version = Some(host_rust_version(detected, rule));
+            }
+        }
+
+        if resolved.platform_tools_version.is_none()
+            && let Some(release) = assessment.sbf_toolchain
+        {
+            resolved.platform_tools_version = Some(release.platform_tools.to_string());
+        }
+
+        if resolved
+            .solana_version
+            .as_ref()
+            .is_none_or(|version| version == "*")
+        {
+            warnings.push("Solana version could not be determined exactly. Suggesting latest known compatible version.".to_string());
+            resolved.solana_version = Some(assessment.latest_rule.solana.clone());
+        }
+
+        if resolved.anchor_version.as_deref() == Some("*") {
+            warnings.push("Anchor version could not be determined exactly. Suggesting latest known compatible version.".to_string());
+            resolved.anchor_version = Some(assessment.latest_rule.anchor.clone());
+        }
+
+        warnings.extend(check_rust_version(detected, &assessment));
+
+        if assessment.inferred_fields.rust_version && assessment.matched_rule.is_none() {
+            warnings.push("Rust version could not be determined exactly. Suggesting latest known compatible version.".to_string());
+            resolved.rust_version = Some(host_rust_version(detected, &assessment.latest_rule));
+        }
+
+        Ok((resolved, assessment, warnings))
+    }
+}
+
+/// The lower bound of a lone caret or tilde requirement, which is what a bare
+/// `Cargo.toml` version such as `2.1.0` becomes. Such a requirement names the
+/// release line the project was written against, even though it admits newer
+/// ones.
+fn caret_release_line(requirement: &VersionReq) -> Option<Version> {
+    match requirement.comparators.as_slice() {
+        [comparator] if matches!(comparator.op, Op::Caret | Op::Tilde) => {
+            requirement_lower_bound(requirement)
+        }
+        _ => None,
+    }
+}
+
+enum VersionQuery {
+    Exact(Version),
+    Requirement(VersionReq),
+}
+
+fn query_from_requirement(requirement: &VersionReq) -> VersionQuery {
+    if let [comparator] = requirement.comparators.as_slice()
+        && comparator.op == Op::Exact
+        && let (Some(minor), Some(patch)) = (comparator.minor, comparator.patch)
     {
-        warnings.push("Solana version could not be determined exactly. Suggesting latest known compatible version.".to_string());
-        resolved.solana_version = Some(assessment.latest_rule.solana.to_string());
+        return VersionQuery::Exact(Version {
+            major: comparator.major,
+            minor,
+            patch,
+            pre: comparator.pre.clone(),
+            build: BuildMetadata::EMPTY,
+        });
     }
 
-    if resolved.rust_version.is_none() {
-        warnings.push("Rust version could not be determined exactly. Suggesting latest known compatible version.".to_string());
-        resolved.rust_version = Some(assessment.latest_rule.rust.to_string());
+    VersionQuery::Requirement(requirement.clone())
+}
+
+fn parse_version_query(version: &str) -> Option<VersionQuery> {
+    let trimmed = version.trim();
+    let bare = trimmed
+        .trim_start_matches('=')
+        .trim_start_matches('v')
+        .trim();
+
+    if let Ok(exact) = Version::parse(bare) {
+        return Some(VersionQuery::Exact(exact));
     }
 
-    Ok((resolved, assessment, warnings))
+    if !bare.is_empty() && bare.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return VersionReq::parse(&format!("={bare}"))
+            .ok()
+            .map(VersionQuery::Requirement);
+    }
+
+    VersionReq::parse(trimmed.trim_start_matches('v'))
+        .ok()
+        .map(VersionQuery::Requirement)
+}
+
+fn same_release(left: &Version, right: &Version) -> bool {
+    left.major == right.major
+        && left.minor == right.minor
+        && left.patch == right.patch
+        && left.pre == right.pre
+}
+
+/// Pick the rule with the highest version, keeping the earliest (newest
+/// Anchor) rule when several share that version.
+fn newest<'a>(
+    rules: impl Iterator<Item = (&'a CompatibilityRule, Version)>,
+) -> Option<&'a CompatibilityRule> {
+    rules
+        .fold(
+            None,
+            |best: Option<(&CompatibilityRule, Version)>, candidate| match best {
+                Some(current) if current.1 >= candidate.1 => Some(current),
+                _ => Some(candidate),
+            },
+        )
+        .map(|(rule, _)| rule)
+}
+
+/// Warn when a TypeScript Anchor client belongs to a different release line
+/// than the one `rule` ships with. Mismatched clients commonly fail to decode
+/// the program's IDL.
+#[must_use]
+pub fn check_ts_client(client: &TsClientVersions, rule: &CompatibilityRule) -> Option<String> {
+    let package = client.anchor.as_ref()?;
+    let version = package.version();
+    // Dist-tags such as `latest` and protocols such as `workspace:*` or
+    // `file:` do not name a version to compare.
+    if !version.starts_with(|character: char| character.is_ascii_digit()) {
+        return None;
+    }
+    let release_line = |version: &str| {
+        let mut parts = version.split('.');
+        (
+            parts.next().map(str::to_string),
+            parts.next().map(str::to_string),
+        )
+    };
+
+    (release_line(&version) != release_line(&rule.ts_client)).then(|| {
+        format!(
+            "{} {version} in {} does not match Anchor {}, which ships TypeScript client {}; IDL decoding may fail.",
+            package.name,
+            client.package_json.display(),
+            rule.anchor,
+            rule.ts_client
+        )
+    })
+}
+
+/// The detected versions that disagree with `rule`.
+fn find_inconsistencies(
+    detected: &ProjectVersions,
+    rule: &CompatibilityRule,
+) -> Vec<Inconsistency> {
+    let mut inconsistencies = Vec::new();
+    let inconsistency = |field: VersionField, detected: &str| Inconsistency {
+        field,
+        detected: detected.to_string(),
+        expected: rule.version(field).to_string(),
+        rule: rule.clone(),
+    };
+
+    for (field, version, requirement) in [
+        (
+            VersionField::Solana,
+            &detected.solana_version,
+            &detected.requirements.solana,
+        ),
+        (
+            VersionField::Anchor,
+            &detected.anchor_version,
+            &detected.requirements.anchor,
+        ),
+    ] {
+        if let Some(version) = version.as_deref().filter(|version| *version != "*")
+            && let Some(requirement) = requirement
+                .clone()
+                .or_else(|| exact_version_requirement(version))
+            && !rule.satisfies(field, &requirement)
+        {
+            inconsistencies.push(inconsistency(field, version));
+        }
+    }
+
+    // An MSRV older than the rule's Rust version is raised to it rather than
+    // contradicting the rule; see `host_rust_version`.
+    if let Some(ordering) = compare_rust_version(detected, rule)
+        && (ordering == Ordering::Greater
+            || (ordering == Ordering::Less && detected.requirements.rust.is_none()))
+        && let Some(version) = &detected.rust_version
+    {
+        inconsistencies.push(inconsistency(VersionField::Rust, version));
+    }
+
+    inconsistencies
+}
+
+/// How the detected Rust version compares with the versions `rule` works
+/// with: `Less` when it is older than the rule's Rust version, `Greater` when
+/// it is newer than the tested range, or `None` when it cannot be compared.
+/// An MSRV, which is recorded as a `>=` requirement, is compared by its lower
+/// bound against the rule's minimum Rust version, and is only too new when it
+/// admits no tested version.
+fn compare_rust_version(detected: &ProjectVersions, rule: &CompatibilityRule) -> Option<Ordering> {
+    let rust_version = detected.rust_version.as_ref()?;
+    let recommended = Version::parse(&rule.rust).ok()?;
+    let bounded = rust_max_tested(rule).is_some();
+
+    if let Some(minimum) = &detected.requirements.rust {
+        let rule_minimum = requirement_lower_bound(&rule.tested.rust).unwrap_or(recommended);
+        return Some(if bounded && !rule.satisfies(VersionField::Rust, minimum) {
+            Ordering::Greater
+        } else if requirement_lower_bound(minimum).is_some_and(|msrv| msrv < rule_minimum) {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        });
+    }
+
+    let stable = stable_rust_version(rust_version)?;
+    Some(
+        if stable < recommended && !rule.tested.rust.matches(&stable) {
+            Ordering::Less
+        } else if stable > recommended
+            && bounded
+            && !exact_version_requirement(&stable.to_string())
+                .is_some_and(|pin| rule.satisfies(VersionField::Rust, &pin))
+        {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        },
+    )
+}
+
+/// The host toolchain to suggest with `rule`: its Rust version, raised to the
+/// detected MSRV when that is newer. An MSRV is only a lower bound, so it is
+/// never suggested below the rule's Rust version.
+fn host_rust_version(detected: &ProjectVersions, rule: &CompatibilityRule) -> String {
+    let msrv = detected
+        .requirements
+        .rust
+        .as_ref()
+        .and_then(requirement_lower_bound);
+    match (msrv, Version::parse(&rule.rust)) {
+        (Some(msrv), Ok(rust)) if msrv > rust => msrv.to_string(),
+        _ => rule.rust.clone(),
+    }
+}
+
+/// The newest Rust version tested with `rule`, or `None` when the rule gives
+/// no `max_tested` Rust version, in which case its Rust version is only a
+/// minimum.
+fn rust_max_tested(rule: &CompatibilityRule) -> Option<Version> {
+    if exact_version_requirement(&rule.rust).as_ref() == Some(&rule.tested.rust) {
+        return None;
+    }
+    requirement_upper_bound(&rule.tested.rust)
+}
+
+/// Explain a detected Rust version that does not work with the matched rule.
+#[must_use]
+pub fn check_rust_version(
+    detected: &ProjectVersions,
+    assessment: &CompatibilityAssessment,
+) -> Option<String> {
+    let rule = assessment.matched_rule.as_ref()?;
+    let rust_version = detected.rust_version.as_ref()?;
+
+    let kind = if detected.requirements.rust.is_some() {
+        "MSRV "
+    } else {
+        ""
+    };
+
+    match compare_rust_version(detected, rule)? {
+        Ordering::Equal => None,
+        Ordering::Greater => Some(format!(
+            "Rust {kind}{rust_version} is newer than Rust {}, the newest tested with Anchor {} and Solana {}.",
+            rust_max_tested(rule)?,
+            rule.anchor,
+            rule.solana
+        )),
+        Ordering::Less => {
+            let equivalent = assessment
+                .rust_mapping
+                .as_ref()
+                .map(|mapping| format!(" (equivalent to Rust {})", mapping.stable_equivalent))
+                .unwrap_or_default();
+            Some(format!(
+                "Rust {kind}{rust_version}{equivalent} is older than Rust {} required by Anchor {} and Solana {}.",
+                rule.rust, rule.anchor, rule.solana
+            ))
+        }
+    }
+}
+
+/// The stable release a detected Rust toolchain name corresponds to.
+fn stable_rust_version(toolchain: &str) -> Option<Version> {
+    stable_equivalent(
+        parse_rust_toolchain_spec(toolchain)
+            .ok()?
+            .channel
+            .as_ref()?,
+    )
+}
+
+fn rust_version_mapping(toolchain: &str) -> Option<RustVersionMapping> {
+    let channel = parse_rust_toolchain_spec(toolchain).ok()?.channel?;
+    if !matches!(
+        channel,
+        ToolchainChannel::Nightly { .. } | ToolchainChannel::Beta { .. }
+    ) {
+        return None;
+    }
+
+    Some(RustVersionMapping {
+        toolchain: toolchain.to_string(),
+        stable_equivalent: stable_equivalent(&channel)?.to_string(),
+    })
 }
 
 fn build_assessment(
     detected: &ProjectVersions,
-    matched_rule: Option<&'static CompatibilityRule>,
+    matched: Option<RuleMatch<'_>>,
+    latest_rule: &CompatibilityRule,
     reason: CompatibilityReason,
+    fallback_distance: Option<VersionDistance>,
 ) -> CompatibilityAssessment {
-    let latest_rule = latest_compatible_rule();
+    let matched_rule = matched.map(|matched| matched.rule);
+    let sbf_toolchain = detected.platform_tools_version.as_deref().map_or_else(
+        || {
+            detected
+                .solana_version
+                .as_deref()
+                .filter(|version| *version != "*")
+                .and_then(platform_tools_for_solana)
+                .or_else(|| matched_rule.and_then(|rule| platform_tools_for_solana(&rule.solana)))
+        },
+        find_platform_tools,
+    );
 
     CompatibilityAssessment {
-        matched_rule,
-        latest_rule,
+        matched_rule: matched_rule.cloned(),
+        match_kind: matched.map(|matched| matched.kind),
+        latest_rule: latest_rule.clone(),
         reason,
         confidence: match reason {
             CompatibilityReason::ExactAnchorMatch | CompatibilityReason::ExactSolanaMatch => {
                 Confidence::High
             }
-            CompatibilityReason::FallbackLatestKnown => Confidence::Low,
+            CompatibilityReason::FallbackNearestKnown => Confidence::Medium,
+            CompatibilityReason::Conflict | CompatibilityReason::FallbackLatestKnown => {
+                Confidence::Low
+            }
         },
+        fallback_distance,
+        candidates: Vec::new(),
         inferred_fields: InferredFields {
-            rust_version: detected.rust_version.is_none(),
+            rust_version: detected.rust_version.is_none() || detected.requirements.rust.is_some(),
             solana_version: detected
                 .solana_version
                 .as_ref()
                 .is_none_or(|version| version == "*")
                 && matched_rule.is_some(),
-            anchor_version: detected.anchor_version.is_none() && matched_rule.is_some(),
+            anchor_version: detected
+                .anchor_version
+                .as_ref()
+                .is_none_or(|version| version == "*")
+                && matched_rule.is_some(),
         },
+        rust_mapping: detected
+            .rust_version
+            .as_deref()
+            .and_then(rust_version_mapping),
+        sbf_toolchain,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::VersionRequirements;
+
+    /// Rules with tested Solana and Rust ranges, which the built-in rules do
+    /// not state.
+    fn tested_range_matrix() -> CompatibilityMatrix {
+        CompatibilityMatrix::from_toml_str(
+            r#"
+[[rules]]
+anchor = "0.30.1"
+solana = { min = "1.17.0", recommended = "1.18.17", max_tested = "1.18.26" }
+rust = { min = "1.76.0", recommended = "1.76.0", max_tested = "1.79.0" }
+
+[[rules]]
+anchor = "0.29.0"
+solana = { min = "1.16.0", recommended = "1.17.0", max_tested = "1.18.26" }
+rust = { min = "1.69.0", recommended = "1.69.0", max_tested = "1.79.0" }
+"#,
+        )
+        .unwrap()
+    }
 
     #[test]
     fn test_find_rule_by_anchor() {
@@ -280,12 +932,55 @@ mod tests {
         assert_eq!(rule.rust, "1.89.0");
     }
 
+    #[test]
+    fn test_platform_tools_follow_solana_release_line() {
+        let release = platform_tools_for_solana("1.18.26").unwrap();
+        assert_eq!(release.platform_tools, "v1.41");
+        assert_eq!(release.rustc, "1.75.0");
+        assert_eq!(platform_tools_for_solana("^2.1").unwrap().rustc, "1.79.0");
+        assert_eq!(platform_tools_for_solana("1.14.0"), None);
+        assert_eq!(
+            platform_tools_for_solana("3.0.8").unwrap().platform_tools,
+            "v1.50"
+        );
+        assert_eq!(
+            platform_tools_for_solana("2.2.14").unwrap().platform_tools,
+            "v1.45"
+        );
+        assert_eq!(find_platform_tools("1.43").unwrap().solana, "2.1");
+    }
+
+    #[test]
+    fn test_host_rust_and_sbf_toolchain_are_resolved_separately() {
+        let detected = ProjectVersions {
+            anchor_version: Some("0.30.1".to_string()),
+            ..ProjectVersions::default()
+        };
+        let (resolved, assessment, _) = resolve_versions(&detected).unwrap();
+        assert_eq!(resolved.rust_version.as_deref(), Some("1.76.0"));
+        assert_eq!(resolved.platform_tools_version.as_deref(), Some("v1.41"));
+        assert_eq!(
+            assessment.sbf_toolchain.map(|release| release.rustc),
+            Some("1.75.0")
+        );
+
+        let detected = ProjectVersions {
+            platform_tools_version: Some("v1.43".to_string()),
+            ..detected
+        };
+        let (resolved, assessment, _) = resolve_versions(&detected).unwrap();
+        assert_eq!(resolved.platform_tools_version.as_deref(), Some("v1.43"));
+        assert_eq!(
+            assessment.sbf_toolchain.map(|release| release.rustc),
+            Some("1.79.0")
+        );
+    }
+
     #[test]
     fn test_resolve_versions_from_anchor() {
         let detected = ProjectVersions {
-            rust_version: None,
-            solana_version: None,
             anchor_version: Some("0.30.1".to_string()),
+            ..ProjectVersions::default()
         };
 
         let (resolved, assessment, warnings) = resolve_versions(&detected).unwrap();
@@ -295,27 +990,317 @@ mod tests {
         assert!(warnings.is_empty());
     }
 
+    #[test]
+    fn test_nightly_toolchain_is_checked_against_rule() {
+        let detected = ProjectVersions {
+            rust_version: Some("nightly-2023-10-29".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let (_, assessment, warnings) = resolve_versions(&detected).unwrap();
+        let mapping = assessment.rust_mapping.unwrap();
+        assert_eq!(mapping.toolchain, "nightly-2023-10-29");
+        assert_eq!(mapping.stable_equivalent, "1.75.0");
+        assert_eq!(
+            warnings,
+            vec![
+                "Rust nightly-2023-10-29 (equivalent to Rust 1.75.0) is older than Rust 1.76.0 required by Anchor 0.30.1 and Solana 1.18.17."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_msrv_above_rule_rust_version_warns() {
+        let detected = ProjectVersions {
+            rust_version: Some("1.80".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            requirements: VersionRequirements {
+                rust: Some(VersionReq::parse(">=1.80").unwrap()),
+                ..VersionRequirements::default()
+            },
+            ..ProjectVersions::default()
+        };
+
+        let (resolved, _, warnings) = tested_range_matrix().resolve_versions(&detected).unwrap();
+        assert_eq!(resolved.rust_version.as_deref(), Some("1.80.0"));
+        assert_eq!(
+            warnings,
+            vec![
+                "Rust MSRV 1.80 is newer than Rust 1.79.0, the newest tested with Anchor 0.30.1 and Solana 1.18.17."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_msrv_below_rule_rust_version_raises_host_toolchain() {
+        let detected = ProjectVersions {
+            rust_version: Some("1.70".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            requirements: VersionRequirements {
+                rust: Some(VersionReq::parse(">=1.70").unwrap()),
+                ..VersionRequirements::default()
+            },
+            ..ProjectVersions::default()
+        };
+
+        let (resolved, assessment, warnings) = resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::ExactAnchorMatch);
+        assert_eq!(resolved.rust_version.as_deref(), Some("1.76.0"));
+        assert_eq!(
+            warnings,
+            vec![
+                "Rust MSRV 1.70 is older than Rust 1.76.0 required by Anchor 0.30.1 and Solana 1.18.17."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ts_client_from_another_release_line_warns() {
+        let client = |version: &str| TsClientVersions {
+            package_json: std::path::PathBuf::from("package.json"),
+            lockfile: None,
+            anchor: Some(crate::types::TsPackage {
+                name: "@coral-xyz/anchor".to_string(),
+                requirement: format!("^{version}"),
+                resolved: None,
+            }),
+            web3: None,
+        };
+        let rule = find_rule_by_anchor("0.30.1").unwrap();
+
+        assert_eq!(check_ts_client(&client("0.30.0"), rule), None);
+        assert_eq!(
+            check_ts_client(&client("0.29.0"), rule).as_deref(),
+            Some(
+                "@coral-xyz/anchor 0.29.0 in package.json does not match Anchor 0.30.1, which ships TypeScript client 0.30.1; IDL decoding may fail."
+            )
+        );
+        for specifier in ["latest", "workspace:*", "file:../anchor/ts", "next"] {
+            assert_eq!(check_ts_client(&client(specifier), rule), None);
+        }
+    }
+
+    #[test]
+    fn test_recent_nightly_toolchain_passes_rule() {
+        let detected = ProjectVersions {
+            rust_version: Some("nightly-2024-01-15".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let (_, assessment, warnings) = resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.rust_mapping.unwrap().stable_equivalent, "1.77.0");
+        assert!(warnings.is_empty());
+    }
+
     #[test]
     fn test_fallback_for_unknown_solana_version() {
         let detected = ProjectVersions {
-            rust_version: None,
             solana_version: Some("*".to_string()),
             anchor_version: Some("9.9.9".to_string()),
+            ..ProjectVersions::default()
         };
 
         let (resolved, assessment, warnings) = resolve_versions(&detected).unwrap();
         assert_eq!(
             resolved.solana_version.as_deref(),
-            Some(latest_compatible_rule().solana)
+            Some(latest_compatible_rule().solana.as_str())
         );
         assert_eq!(
             resolved.rust_version.as_deref(),
-            Some(latest_compatible_rule().rust)
+            Some(latest_compatible_rule().rust.as_str())
         );
         assert_eq!(assessment.reason, CompatibilityReason::FallbackLatestKnown);
         assert_eq!(warnings.len(), 2);
     }
 
+    #[test]
+    fn test_nearest_fallback_policies_report_distance() {
+        let matrix = CompatibilityMatrix::built_in();
+        let detected = ProjectVersions {
+            anchor_version: Some("0.33.0".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let (resolved, assessment, warnings) = matrix
+            .resolve_versions_with(&detected, FallbackPolicy::NearestLower)
+            .unwrap();
+        assert_eq!(
+            warnings[0],
+            "No compatibility rule covers Anchor 0.33.0. Suggesting the nearest known rule, Anchor 0.32.1."
+        );
+        assert_eq!(assessment.reason, CompatibilityReason::FallbackNearestKnown);
+        assert_eq!(assessment.confidence, Confidence::Medium);
+        assert_eq!(assessment.match_kind, Some(MatchKind::Nearest));
+        assert_eq!(resolved.solana_version.as_deref(), Some("2.3.0"));
+        assert_eq!(
+            assessment.fallback_distance,
+            Some(VersionDistance {
+                field: VersionField::Anchor,
+                requested: "0.33.0".to_string(),
+                major: 0,
+                minor: 1,
+                patch: 1,
+            })
+        );
+
+        let assessment = matrix
+            .assess_versions_with(&detected, FallbackPolicy::NearestHigher)
+            .unwrap();
+        assert_eq!(assessment.matched_rule.unwrap().anchor, "1.0.0");
+
+        let assessment = matrix.assess_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::FallbackLatestKnown);
+        assert_eq!(assessment.confidence, Confidence::Low);
+        assert_eq!(assessment.fallback_distance, None);
+
+        let error = matrix
+            .assess_versions_with(&detected, FallbackPolicy::Strict)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No compatibility rule matches Solana (unknown) or Anchor 0.33.0."
+        );
+    }
+
+    #[test]
+    fn test_conflicting_versions_report_inconsistencies() {
+        let detected = ProjectVersions {
+            rust_version: Some("1.89.0".to_string()),
+            solana_version: Some("2.1.0".to_string()),
+            anchor_version: Some("0.29.0".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let (_, assessment, warnings) = resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::Conflict);
+        assert_eq!(assessment.confidence, Confidence::Low);
+        assert_eq!(assessment.matched_rule.as_ref().unwrap().anchor, "0.31.1");
+        let found: Vec<_> = assessment
+            .candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.reason,
+                    candidate.rule.anchor.as_str(),
+                    candidate
+                        .inconsistencies
+                        .iter()
+                        .map(|inconsistency| {
+                            (
+                                inconsistency.field,
+                                inconsistency.detected.as_str(),
+                                inconsistency.expected.as_str(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    CompatibilityReason::ExactSolanaMatch,
+                    "0.31.1",
+                    vec![(VersionField::Anchor, "0.29.0", "0.31.1")]
+                ),
+                (
+                    CompatibilityReason::ExactAnchorMatch,
+                    "0.29.0",
+                    vec![(VersionField::Solana, "2.1.0", "1.17.0")]
+                ),
+            ]
+        );
+        assert_eq!(
+            warnings[0],
+            "Anchor 0.29.0 does not match Anchor 0.31.1 in the compatibility rule for Anchor 0.31.1."
+        );
+    }
+
+    #[test]
+    fn test_consistent_anchor_match_is_preferred_over_solana_match() {
+        let detected = ProjectVersions {
+            solana_version: Some("2.1.0".to_string()),
+            anchor_version: Some("0.31.0".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let assessment = assess_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::ExactAnchorMatch);
+        assert_eq!(assessment.confidence, Confidence::High);
+        assert_eq!(assessment.matched_rule.unwrap().anchor, "0.31.0");
+        let candidates: Vec<_> = assessment
+            .candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.rule.anchor.as_str(),
+                    candidate.inconsistencies.len(),
+                )
+            })
+            .collect();
+        assert_eq!(candidates, vec![("0.31.1", 1), ("0.31.0", 0)]);
+    }
+
+    #[test]
+    fn test_solana_within_anchor_tested_range_is_consistent() {
+        let detected = ProjectVersions {
+            solana_version: Some("1.18.26".to_string()),
+            anchor_version: Some("0.29.0".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let (resolved, assessment, warnings) =
+            tested_range_matrix().resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::ExactAnchorMatch);
+        assert_eq!(assessment.confidence, Confidence::High);
+        assert_eq!(assessment.matched_rule.unwrap().anchor, "0.29.0");
+        assert_eq!(resolved.solana_version.as_deref(), Some("1.18.26"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_rust_newer_than_tested_range_is_inconsistent() {
+        let detected = ProjectVersions {
+            rust_version: Some("1.89.0".to_string()),
+            anchor_version: Some("0.29.0".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let (_, assessment, warnings) = tested_range_matrix().resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::Conflict);
+        assert_eq!(
+            assessment.candidates[0].inconsistencies[0].field,
+            VersionField::Rust
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "Rust 1.89.0 is newer than Rust 1.79.0, the newest tested with Anchor 0.29.0 and Solana 1.17.0."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_msrv_within_tested_rust_range_is_consistent() {
+        let detected = ProjectVersions {
+            rust_version: Some("1.79".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            requirements: VersionRequirements {
+                rust: Some(VersionReq::parse(">=1.79").unwrap()),
+                ..VersionRequirements::default()
+            },
+            ..ProjectVersions::default()
+        };
+
+        let (_, assessment, warnings) = tested_range_matrix().resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.reason, CompatibilityReason::ExactAnchorMatch);
+        assert!(assessment.candidates[0].inconsistencies.is_empty());
+        assert!(warnings.is_empty());
+    }
+
     #[test]
     fn test_patch_release_inherits_previous_minor_compatibility() {
         let rule = find_rule_by_anchor("0.31.1").unwrap();
@@ -323,6 +1308,97 @@ mod tests {
         assert_eq!(rule.rust, "1.84.1");
     }
 
+    #[test]
+    fn test_longer_patch_does_not_match_shorter_rule_exactly() {
+        let matched = match_rule_by_anchor("0.30.10").unwrap();
+        assert_eq!(matched.rule.anchor, "0.30.1");
+        assert_eq!(matched.kind, MatchKind::PatchInherited);
+
+        let matched = match_rule_by_solana("1.18.80").unwrap();
+        assert_eq!(matched.rule.solana, "1.18.17");
+        assert_eq!(matched.kind, MatchKind::PatchInherited);
+    }
+
+    #[test]
+    fn test_exact_match_ignores_build_metadata() {
+        let matched = match_rule_by_anchor("0.30.1+build.5").unwrap();
+        assert_eq!(matched.rule.anchor, "0.30.1");
+        assert_eq!(matched.kind, MatchKind::Exact);
+    }
+
+    #[test]
+    fn test_pre_release_inherits_from_same_minor() {
+        let matched = match_rule_by_anchor("0.31.1-rc.1").unwrap();
+        assert_eq!(matched.rule.anchor, "0.31.1");
+        assert_eq!(matched.kind, MatchKind::PatchInherited);
+
+        assert!(match_rule_by_anchor("0.33.0-rc.1").is_none());
+    }
+
+    #[test]
+    fn test_partial_version_matches_newest_rule_in_minor() {
+        let matched = match_rule_by_solana("1.18").unwrap();
+        assert_eq!(matched.rule.solana, "1.18.17");
+        assert_eq!(matched.kind, MatchKind::Range);
+    }
+
+    #[test]
+    fn test_requirement_matches_newest_satisfying_rule() {
+        let matched = match_rule_by_anchor(">=0.29, <0.31").unwrap();
+        assert_eq!(matched.rule.anchor, "0.30.1");
+        assert_eq!(matched.kind, MatchKind::Range);
+    }
+
+    #[test]
+    fn test_caret_requirement_prefers_its_own_release_line() {
+        let detected = ProjectVersions {
+            solana_version: Some("2.1.0".to_string()),
+            requirements: crate::types::VersionRequirements {
+                solana: crate::parse::parse_version_requirement("2.1.0"),
+                ..Default::default()
+            },
+            ..ProjectVersions::default()
+        };
+
+        let (resolved, assessment, _) = resolve_versions(&detected).unwrap();
+        assert_eq!(assessment.matched_rule.unwrap().solana, "2.1.0");
+        assert_eq!(resolved.anchor_version.as_deref(), Some("0.31.1"));
+
+        let matched = match_rule_by_anchor("^0.30.0").unwrap();
+        assert_eq!(matched.rule.anchor, "0.30.1");
+    }
+
+    #[test]
+    fn test_solana_match_prefers_newest_anchor_for_shared_version() {
+        let matched = match_rule_by_solana("3.1.10").unwrap();
+        assert_eq!(matched.rule.anchor, latest_compatible_rule().anchor);
+        assert_eq!(matched.kind, MatchKind::Exact);
+    }
+
+    #[test]
+    fn test_assessment_uses_full_requirement() {
+        let detected = ProjectVersions {
+            solana_version: Some("1.18".to_string()),
+            requirements: crate::types::VersionRequirements {
+                solana: Some(VersionReq::parse(">=1.18, <=2").unwrap()),
+                ..Default::default()
+            },
+            ..ProjectVersions::default()
+        };
+
+        let assessment = assess_versions(&detected).unwrap();
+        assert_eq!(assessment.matched_rule.unwrap().solana, "2.3.0");
+        assert_eq!(assessment.match_kind, Some(MatchKind::Range));
+    }
+
+    #[test]
+    fn test_exact_requirement_uses_patch_inheritance() {
+        let matched =
+            match_rule_by_solana_requirement(&VersionReq::parse("=1.18.30").unwrap()).unwrap();
+        assert_eq!(matched.rule.solana, "1.18.17");
+        assert_eq!(matched.kind, MatchKind::PatchInherited);
+    }
+
     #[test]
     fn test_corrected_anchor_032_compatibility() {
         let rule = find_rule_by_anchor("0.32.1").unwrap();
@@ -0,0 +1,73 @@
+use crate::types::{KnownRepository, RecognizedCrate, RepositoryKind, VersionField};
+
+const fn solana(name: &'static str, weight: u8) -> RecognizedCrate {
+    RecognizedCrate {
+        name,
+        field: VersionField::Solana,
+        weight,
+    }
+}
+
+const fn anchor(name: &'static str, weight: u8) -> RecognizedCrate {
+    RecognizedCrate {
+        name,
+        field: VersionField::Anchor,
+        weight,
+    }
+}
+
+/// Crates whose versions are read as Solana or Anchor version signals.
+///
+/// `solana-program` and `anchor-lang` track their releases exactly. Client,
+/// test and `agave-*` crates follow the Agave release line. The 2.x/3.x split
+/// crates are versioned independently, so they only count when nothing
+/// stronger is present.
+const RECOGNIZED_CRATES: [RecognizedCrate; 20] = [
+    solana("solana-program", 100),
+    solana("solana-sdk", 90),
+    solana("solana-program-test", 80),
+    solana("solana-client", 80),
+    solana("solana-rpc-client", 80),
+    solana("solana-banks-client", 70),
+    solana("solana-runtime", 70),
+    solana("agave-*", 60),
+    solana("solana-pubkey", 30),
+    solana("solana-account-info", 30),
+    solana("solana-instruction", 30),
+    solana("solana-program-error", 30),
+    solana("solana-program-entrypoint", 30),
+    solana("solana-msg", 30),
+    solana("solana-sysvar", 30),
+    solana("solana-cpi", 30),
+    solana("solana-signer", 30),
+    anchor("anchor-lang", 100),
+    anchor("anchor-spl", 90),
+    anchor("anchor-client", 80),
+];
+
+#[must_use]
+pub const fn recognized_crates() -> &'static [RecognizedCrate] {
+    &RECOGNIZED_CRATES
+}
+
+const fn repository(slug: &'static str, kind: RepositoryKind) -> KnownRepository {
+    KnownRepository { slug, kind }
+}
+
+/// Git repositories Anchor and Solana crates are commonly pulled from.
+///
+/// Anchor moved from `project-serum` to `coral-xyz` and then to
+/// `solana-foundation`; all three are treated as upstream.
+const KNOWN_REPOSITORIES: [KnownRepository; 6] = [
+    repository("solana-foundation/anchor", RepositoryKind::Upstream),
+    repository("coral-xyz/anchor", RepositoryKind::Upstream),
+    repository("project-serum/anchor", RepositoryKind::Upstream),
+    repository("otter-sec/anchor", RepositoryKind::Fork),
+    repository("anza-xyz/agave", RepositoryKind::Upstream),
+    repository("solana-labs/solana", RepositoryKind::Upstream),
+];
+
+#[must_use]
+pub const fn known_repositories() -> &'static [KnownRepository] {
+    &KNOWN_REPOSITORIES
+}
@@ -1,33 +1,81 @@
-use anyhow::{anyhow, Result};
+use anyhow::{Result, anyhow};
+use semver::VersionReq;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::compatibility::resolve_versions;
-use crate::parse::{parse_anchor_toml, parse_cargo_toml, parse_rust_toolchain};
+use crate::compatibility::{check_rust_version, check_ts_client};
+use crate::elf::{parse_program_binary, program_binary_signals};
+use crate::parse::{
+    clean_version, dependency_signals, exact_version_requirement, parse_anchor_config,
+    parse_anchor_toml, parse_anchor_version_file, parse_cargo_lock_dependencies,
+    parse_cargo_manifest, parse_idl, parse_mise_toml, parse_package_json, parse_package_lock,
+    parse_pnpm_lock, parse_rust_toolchain_spec, parse_semver_range, parse_tool_versions,
+    parse_yarn_lock, strongest_signal,
+};
+use crate::text_signals::{extract_text_signals, text_signal_kind};
 use crate::types::{
-    DetectionReport, ProjectVersions, ScanOptions, VersionField, VersionSource, VersionSourceKind,
+    CargoManifest, CompatibilityMatrix, ConstraintSolution, DependencyOrigin, DependencySignal,
+    DependencyTable, DetectionReport, DirectoryScan, ManifestDependency, ScanOptions,
+    TsClientVersions, TsPackage, VersionField, VersionSource, VersionSourceKind,
 };
 
 const RUST_TOOLCHAIN_FILES: &[&str] = &["rust-toolchain", "rust-toolchain.toml"];
+const TOOL_VERSION_FILES: &[(&str, VersionSourceKind)] = &[
+    (".tool-versions", VersionSourceKind::ToolVersions),
+    ("mise.toml", VersionSourceKind::MiseToml),
+    (".mise.toml", VersionSourceKind::MiseToml),
+];
 const MAX_RUST_TOOLCHAIN_FILE_SIZE: usize = 10_000;
+const MAX_ANCHOR_VERSION_FILE_SIZE: usize = 10_000;
 const MAX_TOML_FILE_SIZE: usize = 100_000;
+const MAX_CARGO_LOCK_FILE_SIZE: usize = 5_000_000;
+const MAX_PACKAGE_JSON_FILE_SIZE: usize = 1_000_000;
+const MAX_JS_LOCKFILE_SIZE: usize = 20_000_000;
+const MAX_IDL_FILE_SIZE: usize = 5_000_000;
+const MAX_PROGRAM_BINARY_SIZE: usize = 50_000_000;
+const MAX_TEXT_SIGNAL_FILE_SIZE: usize = 1_000_000;
+
+const IDL_DIRECTORIES: &[&str] = &["target/idl", "idl"];
+const DEPLOY_DIRECTORY: &str = "target/deploy";
+const WORKFLOW_DIRECTORY: &str = ".github/workflows";
+
+const TS_ANCHOR_PACKAGES: &[&str] = &["@coral-xyz/anchor", "@project-serum/anchor"];
+const TS_WEB3_PACKAGE: &str = "@solana/web3.js";
+const JS_LOCKFILES: &[&str] = &["yarn.lock", "package-lock.json", "pnpm-lock.yaml"];
 
 /// Detect version signals from files in a single directory.
 ///
 /// # Errors
 ///
 /// Returns an error when project files cannot be read or exceed enforced size limits.
-pub fn detect_versions_in_dir(
+pub fn detect_versions_in_dir(project_path: &Path) -> Result<DirectoryScan> {
+    detect_versions_in_dir_with(project_path, &ScanOptions::default())
+}
+
+/// Detect version signals from files in a single directory using the crate
+/// table and other settings from `options`.
+///
+/// # Errors
+///
+/// Returns an error when project files cannot be read or exceed enforced size limits.
+pub fn detect_versions_in_dir_with(
     project_path: &Path,
-) -> Result<(ProjectVersions, Vec<VersionSource>)> {
-    let mut versions = ProjectVersions::default();
-    let mut sources = Vec::new();
+    options: &ScanOptions,
+) -> Result<DirectoryScan> {
+    let mut scan = DirectoryScan::default();
 
-    check_rust_toolchain_files(project_path, &mut versions, &mut sources)?;
-    check_anchor_toml(project_path, &mut versions, &mut sources)?;
-    check_cargo_toml(project_path, &mut versions, &mut sources)?;
+    check_rust_toolchain_files(project_path, &mut scan)?;
+    check_anchor_toml(project_path, &mut scan)?;
+    check_anchor_version_file(project_path, &mut scan)?;
+    check_tool_version_files(project_path, &mut scan)?;
+    check_cargo_lock(project_path, options, &mut scan)?;
+    check_cargo_toml(project_path, options, &mut scan)?;
+    check_package_json(project_path, &mut scan)?;
+    check_idl_files(project_path, &mut scan)?;
+    check_text_signal_files(project_path, options, &mut scan)?;
 
-    Ok((versions, sources))
+    Ok(scan)
 }
 
 /// Detect versions for a project path, optionally recursing into subdirectories.
@@ -41,23 +89,143 @@ pub fn detect_versions_recursive(
     options: &ScanOptions,
 ) -> Result<DetectionReport> {
     let project_path = validate_project_path(project_path)?;
-    let (mut detected, mut sources) = detect_versions_in_dir(&project_path)?;
+    let mut scan = detect_versions_in_dir_with(&project_path, options)?;
+
+    if options.recursive && scan.versions.needs_more_info() {
+        search_subdirectories(&project_path, options, &mut scan)?;
+    }
+    dedup_sources(&mut scan.sources);
+    apply_text_signals(&mut scan);
+    apply_idl_signals(&mut scan);
+
+    build_report(scan, options)
+}
+
+/// Detect versions from compiled SBF programs.
+///
+/// `path` may name a program binary directly, or a project directory whose
+/// `target/deploy/*.so` files are inspected. The rustc version a binary
+/// reports is the SBF compiler from platform-tools, so it is recorded as a
+/// source but never taken as the host Rust version.
+///
+/// # Errors
+///
+/// Returns an error when the path does not exist, a binary cannot be read,
+/// exceeds the size limit or is not an ELF file, or a directory contains no
+/// deployed programs.
+pub fn detect_versions_in_binary(path: &Path, options: &ScanOptions) -> Result<DetectionReport> {
+    if !path.exists() {
+        return Err(anyhow!("Path does not exist: {}", path.display()));
+    }
+
+    let binaries = if path.is_dir() {
+        let deploy_dir = path.join(DEPLOY_DIRECTORY);
+        let mut binaries: Vec<PathBuf> = fs::read_dir(&deploy_dir)
+            .map_err(|error| {
+                anyhow!(
+                    "Failed to read directory {}: {}",
+                    deploy_dir.display(),
+                    error
+                )
+            })?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "so"))
+            .collect();
+        binaries.sort();
+        binaries
+    } else {
+        vec![path.to_path_buf()]
+    };
 
-    if options.recursive && detected.needs_more_info() {
-        search_subdirectories(&project_path, options, &mut detected, &mut sources)?;
+    if binaries.is_empty() {
+        return Err(anyhow!(
+            "No program binaries found in {}",
+            path.join(DEPLOY_DIRECTORY).display()
+        ));
+    }
+
+    let mut scan = DirectoryScan::default();
+    for binary in binaries {
+        check_program_binary(&binary, options, &mut scan)?;
+    }
+    dedup_sources(&mut scan.sources);
+
+    build_report(scan, options)
+}
+
+fn build_report(scan: DirectoryScan, options: &ScanOptions) -> Result<DetectionReport> {
+    let matrix: &CompatibilityMatrix = &options.matrix;
+    let solution = matrix.solve_constraints(&solver_sources(&scan));
+    let (resolved, compatibility, resolve_warnings) =
+        matrix.resolve_versions_with(&scan.versions, options.fallback_policy)?;
+    let mut warnings = scan.warnings;
+    warnings.extend(resolve_warnings);
+
+    let ts_rule = resolved
+        .anchor_version
+        .as_deref()
+        .and_then(|anchor| matrix.find_rule_by_anchor(anchor))
+        .or(compatibility.matched_rule.as_ref());
+    if let Some(client) = &scan.ts_client
+        && let Some(rule) = ts_rule
+    {
+        warnings.extend(check_ts_client(client, rule));
     }
 
-    let (resolved, compatibility, warnings) = resolve_versions(&detected)?;
+    if let ConstraintSolution::Unsatisfiable(core) = &solution {
+        // The core names the manifests behind a Rust conflict, so it replaces
+        // the resolver's warning about the same Rust version.
+        if core
+            .constraints
+            .iter()
+            .any(|constraint| constraint.field == VersionField::Rust)
+            && let Some(rust_warning) = check_rust_version(&scan.versions, &compatibility)
+        {
+            warnings.retain(|warning| *warning != rust_warning);
+        }
+        warnings.push(core.to_string());
+    }
 
     Ok(DetectionReport {
-        detected,
+        detected: scan.versions,
         resolved,
         compatibility,
-        sources,
+        rust_toolchain: scan.rust_toolchain,
+        anchor_config: scan.anchor_config,
+        ts_client: scan.ts_client,
+        program_binaries: scan.program_binaries,
+        text_signals: scan.text_signals,
+        sources: scan.sources,
+        solution,
         warnings,
     })
 }
 
+/// The sources whose requirements constrain the workspace: every manifest and
+/// lockfile, plus each pin that supplied a detected version. Pins overridden
+/// by a higher-precedence source are left out, since precedence already
+/// settled them and any disagreement is warned about where it is found.
+fn solver_sources(scan: &DirectoryScan) -> Vec<VersionSource> {
+    let detected = |field: VersionField| match field {
+        VersionField::Rust => scan.versions.rust_version.as_deref(),
+        VersionField::Solana => scan.versions.solana_version.as_deref(),
+        VersionField::Anchor => scan.versions.anchor_version.as_deref(),
+    };
+
+    scan.sources
+        .iter()
+        .filter(|source| {
+            matches!(
+                source.kind,
+                VersionSourceKind::CargoToml
+                    | VersionSourceKind::CargoLock
+                    | VersionSourceKind::CargoRustVersion
+            ) || detected(source.field) == Some(source.value.as_str())
+        })
+        .cloned()
+        .collect()
+}
+
 fn validate_project_path(project_path: &Path) -> Result<PathBuf> {
     if !project_path.exists() {
         return Err(anyhow!(
@@ -85,8 +253,7 @@ fn validate_project_path(project_path: &Path) -> Result<PathBuf> {
 fn search_subdirectories(
     dir: &Path,
     options: &ScanOptions,
-    versions: &mut ProjectVersions,
-    sources: &mut Vec<VersionSource>,
+    scan: &mut DirectoryScan,
 ) -> Result<()> {
     let entries = fs::read_dir(dir)
         .map_err(|error| anyhow!("Failed to read directory {}: {}", dir.display(), error))?;
@@ -106,15 +273,26 @@ fn search_subdirectories(
             continue;
         }
 
-        let (sub_versions, sub_sources) = detect_versions_in_dir(&path)?;
-        versions.merge_missing_from(&sub_versions);
-        sources.extend(sub_sources);
+        let sub_scan = detect_versions_in_dir_with(&path, options)?;
+        scan.versions.merge_missing_from(&sub_scan.versions);
+        if scan.rust_toolchain.is_none() {
+            scan.rust_toolchain = sub_scan.rust_toolchain;
+        }
+        if scan.anchor_config.is_none() {
+            scan.anchor_config = sub_scan.anchor_config;
+        }
+        if scan.ts_client.is_none() {
+            scan.ts_client = sub_scan.ts_client;
+        }
+        scan.text_signals.extend(sub_scan.text_signals);
+        scan.sources.extend(sub_scan.sources);
+        scan.warnings.extend(sub_scan.warnings);
 
-        if versions.needs_more_info() {
-            search_subdirectories(&path, options, versions, sources)?;
+        if scan.versions.needs_more_info() {
+            search_subdirectories(&path, options, scan)?;
         }
 
-        if !versions.needs_more_info() {
+        if !scan.versions.needs_more_info() {
             break;
         }
     }
@@ -122,11 +300,7 @@ fn search_subdirectories(
     Ok(())
 }
 
-fn check_rust_toolchain_files(
-    project_path: &Path,
-    versions: &mut ProjectVersions,
-    sources: &mut Vec<VersionSource>,
-) -> Result<()> {
+fn check_rust_toolchain_files(project_path: &Path, scan: &mut DirectoryScan) -> Result<()> {
     for filename in RUST_TOOLCHAIN_FILES {
         let path = project_path.join(filename);
         if !path.exists() {
@@ -140,15 +314,19 @@ fn check_rust_toolchain_files(
             return Err(anyhow!("File {} is too large (>10KB)", path.display()));
         }
 
-        if let Ok(version) = parse_rust_toolchain(&content) {
-            set_version(
-                versions,
-                sources,
-                VersionField::Rust,
-                VersionSourceKind::RustToolchain,
-                &path,
-                version,
-            );
+        if let Ok(spec) = parse_rust_toolchain_spec(&content) {
+            if let Some(channel) = &spec.channel {
+                set_version(
+                    scan,
+                    VersionField::Rust,
+                    VersionSourceKind::RustToolchain,
+                    &path,
+                    channel.name(),
+                    None,
+                    None,
+                );
+            }
+            scan.rust_toolchain = Some(spec);
         }
         break;
     }
@@ -156,11 +334,7 @@ fn check_rust_toolchain_files(
     Ok(())
 }
 
-fn check_anchor_toml(
-    project_path: &Path,
-    versions: &mut ProjectVersions,
-    sources: &mut Vec<VersionSource>,
-) -> Result<()> {
+fn check_anchor_toml(project_path: &Path, scan: &mut DirectoryScan) -> Result<()> {
     let path = project_path.join("Anchor.toml");
     if !path.exists() {
         return Ok(());
@@ -173,94 +347,1188 @@ fn check_anchor_toml(
         return Err(anyhow!("File {} is too large (>100KB)", path.display()));
     }
 
+    match parse_anchor_config(&content) {
+        Ok(config) => scan.anchor_config = Some(config),
+        Err(error) => scan.warnings.push(format!(
+            "{} could not be fully parsed: {error}",
+            path.display()
+        )),
+    }
+
     let parsed = parse_anchor_toml(&content);
     if let Some(solana_version) = parsed.solana_version {
         set_version(
-            versions,
-            sources,
+            scan,
             VersionField::Solana,
             VersionSourceKind::AnchorToml,
             &path,
             solana_version,
+            parsed.requirements.solana.clone(),
+            None,
         );
     }
     if let Some(anchor_version) = parsed.anchor_version {
         set_version(
-            versions,
-            sources,
+            scan,
             VersionField::Anchor,
             VersionSourceKind::AnchorToml,
             &path,
             anchor_version,
+            parsed.requirements.anchor.clone(),
+            None,
         );
     }
 
     Ok(())
 }
 
+fn check_anchor_version_file(project_path: &Path, scan: &mut DirectoryScan) -> Result<()> {
+    let path = project_path.join(".anchorversion");
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+    if content.len() > MAX_ANCHOR_VERSION_FILE_SIZE {
+        return Err(anyhow!("File {} is too large (>10KB)", path.display()));
+    }
+
+    let Ok(version) = parse_anchor_version_file(&content) else {
+        scan.warnings.push(format!(
+            "{} does not contain a valid Anchor version.",
+            path.display()
+        ));
+        return Ok(());
+    };
+
+    // Once the override has been reported, the pin is kept as a source
+    // without a requirement so constraint solving does not report it again.
+    let requirement = if let Some(pinned) = scan.sources.iter().find(|source| {
+        source.field == VersionField::Anchor && source.kind == VersionSourceKind::AnchorToml
+    }) && clean_version(&pinned.value) != version
+    {
+        scan.warnings.push(format!(
+            "{} pins Anchor {version} but {} pins Anchor {}; using the Anchor.toml version.",
+            path.display(),
+            pinned.path.display(),
+            pinned.value
+        ));
+        None
+    } else {
+        exact_version_requirement(&version)
+    };
+
+    set_version(
+        scan,
+        VersionField::Anchor,
+        VersionSourceKind::AnchorVersionFile,
+        &path,
+        version,
+        requirement,
+        None,
+    );
+
+    Ok(())
+}
+
+fn check_tool_version_files(project_path: &Path, scan: &mut DirectoryScan) -> Result<()> {
+    for (filename, kind) in TOOL_VERSION_FILES {
+        let path = project_path.join(filename);
+        if !path.exists() {
+            continue;
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+        if content.len() > MAX_TOML_FILE_SIZE {
+            return Err(anyhow!("File {} is too large (>100KB)", path.display()));
+        }
+
+        let parsed = match kind {
+            VersionSourceKind::ToolVersions => parse_tool_versions(&content),
+            _ => parse_mise_toml(&content),
+        };
+        let fields = [
+            (VersionField::Rust, parsed.rust_version),
+            (VersionField::Solana, parsed.solana_version),
+            (VersionField::Anchor, parsed.anchor_version),
+        ];
+        for (field, version) in fields {
+            if let Some(version) = version {
+                set_version(
+                    scan,
+                    field,
+                    *kind,
+                    &path,
+                    version,
+                    parsed.requirements.get(field).cloned(),
+                    None,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_cargo_lock(
+    project_path: &Path,
+    options: &ScanOptions,
+    scan: &mut DirectoryScan,
+) -> Result<()> {
+    let path = project_path.join("Cargo.lock");
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+    if content.len() > MAX_CARGO_LOCK_FILE_SIZE {
+        return Err(anyhow!("File {} is too large (>5MB)", path.display()));
+    }
+
+    let signals = parse_cargo_lock_dependencies(&content, options.recognized_crates);
+    record_dependency_signals(scan, VersionSourceKind::CargoLock, &path, &signals);
+
+    Ok(())
+}
+
 fn check_cargo_toml(
     project_path: &Path,
-    versions: &mut ProjectVersions,
-    sources: &mut Vec<VersionSource>,
+    options: &ScanOptions,
+    scan: &mut DirectoryScan,
 ) -> Result<()> {
     let path = project_path.join("Cargo.toml");
     if !path.exists() {
         return Ok(());
     }
 
+    let manifest = parse_cargo_manifest(&read_cargo_toml(&path)?);
+    let mut signals = dependency_signals(&manifest.dependencies, options.recognized_crates);
+
+    let inherits_dependencies = manifest
+        .dependencies
+        .iter()
+        .any(|dependency| dependency.workspace);
+    let workspace_root = if inherits_dependencies || manifest.inherits_rust_version {
+        find_workspace_root(project_path, &manifest)?
+    } else {
+        None
+    };
+
+    if inherits_dependencies && let Some((root_path, root_manifest)) = &workspace_root {
+        let inherited: Vec<ManifestDependency> = manifest
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.workspace)
+            .filter_map(|dependency| {
+                let root_dependency = root_manifest.dependencies.iter().find(|candidate| {
+                    candidate.table == DependencyTable::Workspace
+                        && candidate.key() == dependency.key()
+                })?;
+                Some(ManifestDependency {
+                    name: root_dependency.name.clone(),
+                    alias: (root_dependency.name != dependency.key())
+                        .then(|| dependency.key().to_string()),
+                    requirement: root_dependency.requirement.clone(),
+                    git: root_dependency.git.clone(),
+                    ..dependency.clone()
+                })
+            })
+            .collect();
+
+        signals.extend(
+            dependency_signals(&inherited, options.recognized_crates)
+                .into_iter()
+                .map(|mut signal| {
+                    signal.origin.inherited_from = Some(root_path.clone());
+                    signal
+                }),
+        );
+    }
+
+    record_rust_version(scan, &path, &manifest, workspace_root.as_ref());
+
+    for dependency in &manifest.dependencies {
+        warn_about_unmapped_git_source(scan, options, &path, dependency);
+    }
+
+    record_dependency_signals(scan, VersionSourceKind::CargoToml, &path, &signals);
+
+    Ok(())
+}
+
+/// Record the Anchor release line implied by each IDL file. IDLs are only
+/// weighed once the whole project has been scanned; see [`apply_idl_signals`].
+fn check_idl_files(project_path: &Path, scan: &mut DirectoryScan) -> Result<()> {
+    for directory in IDL_DIRECTORIES {
+        let Ok(entries) = fs::read_dir(project_path.join(directory)) else {
+            continue;
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let content = fs::read_to_string(&path)
+                .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+            if content.len() > MAX_IDL_FILE_SIZE {
+                return Err(anyhow!("File {} is too large (>5MB)", path.display()));
+            }
+
+            // The implied range is kept as the value only: an IDL is a
+            // low-priority signal, not a requirement for constraint solving.
+            if let Ok(format) = parse_idl(&content) {
+                scan.sources.push(VersionSource {
+                    field: VersionField::Anchor,
+                    kind: VersionSourceKind::Idl,
+                    path,
+                    value: format.anchor_requirement().to_string(),
+                    requirement: None,
+                    dependency: None,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_program_binary(
+    path: &Path,
+    options: &ScanOptions,
+    scan: &mut DirectoryScan,
+) -> Result<()> {
+    let bytes =
+        fs::read(path).map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+    if bytes.len() > MAX_PROGRAM_BINARY_SIZE {
+        return Err(anyhow!("File {} is too large (>50MB)", path.display()));
+    }
+
+    let program = parse_program_binary(&bytes, options.recognized_crates)
+        .map_err(|error| anyhow!("Failed to inspect {}: {}", path.display(), error))?;
+
+    record_dependency_signals(
+        scan,
+        VersionSourceKind::ProgramBinary,
+        path,
+        &program_binary_signals(&program, options.recognized_crates),
+    );
+
+    if let Some(rustc_version) = &program.rustc_version {
+        scan.sources.push(VersionSource {
+            field: VersionField::Rust,
+            kind: VersionSourceKind::ProgramBinary,
+            path: path.to_path_buf(),
+            value: rustc_version.clone(),
+            requirement: None,
+            dependency: None,
+        });
+    }
+
+    if program.crate_versions.is_empty() {
+        scan.warnings.push(format!(
+            "{} does not name any recognised crate versions; it may have been built without debug paths.",
+            path.display()
+        ));
+    }
+
+    if scan.versions.platform_tools_version.is_none() {
+        scan.versions
+            .platform_tools_version
+            .clone_from(&program.platform_tools_version);
+    }
+
+    scan.program_binaries.insert(path.to_path_buf(), program);
+    Ok(())
+}
+
+/// Collect toolchain pins from CI workflows, Dockerfiles and build scripts.
+/// Like IDLs, they are recorded without touching the detected versions and
+/// only applied once the whole project has been scanned.
+fn check_text_signal_files(
+    project_path: &Path,
+    options: &ScanOptions,
+    scan: &mut DirectoryScan,
+) -> Result<()> {
+    let mut paths: Vec<PathBuf> = [
+        project_path.to_path_buf(),
+        project_path.join(WORKFLOW_DIRECTORY),
+    ]
+    .iter()
+    .filter_map(|dir| fs::read_dir(dir).ok())
+    .flatten()
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.is_file())
+    .collect();
+    paths.sort();
+
+    for path in paths {
+        let Some(kind) = path
+            .strip_prefix(project_path)
+            .ok()
+            .and_then(text_signal_kind)
+        else {
+            continue;
+        };
+
+        let content = fs::read_to_string(&path)
+            .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+        if content.len() > MAX_TEXT_SIGNAL_FILE_SIZE {
+            return Err(anyhow!("File {} is too large (>1MB)", path.display()));
+        }
+
+        for signal in extract_text_signals(&content, &path, kind, options.text_signal_patterns) {
+            if let Some(field) = signal.tool.field() {
+                scan.sources.push(VersionSource {
+                    field,
+                    kind,
+                    path: path.clone(),
+                    value: signal.version.clone(),
+                    requirement: None,
+                    dependency: None,
+                });
+            }
+            scan.text_signals.push(signal);
+        }
+    }
+
+    Ok(())
+}
+
+/// Use CI and build script pins for versions no project file supplied, and
+/// warn about pins that disagree with the detected versions.
+fn apply_text_signals(scan: &mut DirectoryScan) {
+    for signal in &scan.text_signals {
+        let Some(field) = signal.tool.field() else {
+            let pinned = format!("v{}", signal.version);
+            match &scan.versions.platform_tools_version {
+                None => scan.versions.platform_tools_version = Some(pinned),
+                Some(detected) if *detected != pinned => scan.warnings.push(format!(
+                    "{} line {} pins platform-tools {pinned}, which disagrees with the detected version {detected}.",
+                    signal.path.display(),
+                    signal.line
+                )),
+                Some(_) => {}
+            }
+            continue;
+        };
+        let versions = &mut scan.versions;
+        let (detected, requirement) = match field {
+            VersionField::Rust => (&mut versions.rust_version, &mut versions.requirements.rust),
+            VersionField::Solana => (
+                &mut versions.solana_version,
+                &mut versions.requirements.solana,
+            ),
+            VersionField::Anchor => (
+                &mut versions.anchor_version,
+                &mut versions.requirements.anchor,
+            ),
+        };
+
+        let Some(detected_version) = detected.as_deref() else {
+            *detected = Some(signal.version.clone());
+            if field != VersionField::Rust {
+                *requirement = exact_version_requirement(&signal.version);
+            }
+            continue;
+        };
+
+        if !pin_agrees(&signal.version, detected_version, requirement.as_ref()) {
+            scan.warnings.push(format!(
+                "{} line {} pins {} {}, which disagrees with the detected version {detected_version}.",
+                signal.path.display(),
+                signal.line,
+                signal.tool.name(),
+                signal.version
+            ));
+        }
+    }
+}
+
+/// Whether a pinned version is consistent with a detected version. A partial
+/// pin such as `1.18` agrees with any `1.18.x`, and a full pin agrees when it
+/// satisfies the detected requirement. Non-numeric detections such as
+/// `nightly` are not compared.
+fn pin_agrees(pinned: &str, detected: &str, requirement: Option<&VersionReq>) -> bool {
+    let components = |version: &str| -> Option<Vec<u64>> {
+        version.split('.').map(|part| part.parse().ok()).collect()
+    };
+    let (Some(pinned_parts), Some(detected_parts)) = (
+        components(pinned),
+        components(&parse_semver_range(detected)),
+    ) else {
+        return true;
+    };
+
+    let same_prefix = pinned_parts
+        .iter()
+        .zip(&detected_parts)
+        .all(|(pinned, detected)| pinned == detected);
+    same_prefix
+        || (requirement.is_some_and(|requirement| {
+            semver::Version::parse(pinned).is_ok_and(|version| requirement.matches(&version))
+        }))
+}
+
+/// Use IDL files as a last-resort Anchor signal, and warn about IDLs whose
+/// format contradicts the Anchor version found elsewhere. This runs after the
+/// whole project has been scanned so that a root `target/idl` never shadows
+/// the `anchor-lang` version of a workspace member.
+fn apply_idl_signals(scan: &mut DirectoryScan) {
+    let idl_requirements: Vec<(PathBuf, VersionReq)> = scan
+        .sources
+        .iter()
+        .filter(|source| source.kind == VersionSourceKind::Idl)
+        .filter_map(|source| {
+            VersionReq::parse(&source.value)
+                .ok()
+                .map(|requirement| (source.path.clone(), requirement))
+        })
+        .collect();
+
+    // The IDL format only implies a range such as `>=0.30.0`, which is not a
+    // version to install, so it is kept as a requirement and the display
+    // version comes from the matched rule.
+    let Some(anchor_version) = scan
+        .versions
+        .anchor_version
+        .as_ref()
+        .filter(|version| *version != "*")
+    else {
+        if scan.versions.requirements.anchor.is_none()
+            && let Some((_, requirement)) = idl_requirements.first()
+        {
+            scan.versions.requirements.anchor = Some(requirement.clone());
+        }
+        return;
+    };
+
+    let lower_bound = parse_semver_range(anchor_version);
+    let Ok(detected) = semver::Version::parse(&lower_bound)
+        .or_else(|_| semver::Version::parse(&format!("{lower_bound}.0")))
+    else {
+        return;
+    };
+    for (path, requirement) in &idl_requirements {
+        if !requirement.matches(&detected) {
+            let format = if requirement.matches(&semver::Version::new(0, 30, 0)) {
+                "0.30+"
+            } else {
+                "pre-0.30 legacy"
+            };
+            scan.warnings.push(format!(
+                "{} uses the {format} IDL format, which contradicts the detected Anchor version {anchor_version}; the IDL may be stale.",
+                path.display()
+            ));
+        }
+    }
+}
+
+/// Read the Anchor and web3.js versions used by a TypeScript client, preferring
+/// the versions pinned by the first lockfile found next to `package.json`.
+fn check_package_json(project_path: &Path, scan: &mut DirectoryScan) -> Result<()> {
+    let path = project_path.join("package.json");
+    if !path.exists() {
+        return Ok(());
+    }
+
     let content = fs::read_to_string(&path)
         .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
 
+    if content.len() > MAX_PACKAGE_JSON_FILE_SIZE {
+        return Err(anyhow!("File {} is too large (>1MB)", path.display()));
+    }
+
+    let Ok(dependencies) = parse_package_json(&content) else {
+        scan.warnings
+            .push(format!("{} is not valid JSON.", path.display()));
+        return Ok(());
+    };
+    let anchor = TS_ANCHOR_PACKAGES
+        .iter()
+        .find_map(|name| dependencies.get_key_value(*name));
+    let web3 = dependencies.get_key_value(TS_WEB3_PACKAGE);
+    if anchor.is_none() && web3.is_none() {
+        return Ok(());
+    }
+
+    let mut lockfile = None;
+    let mut locked = BTreeMap::new();
+    for filename in JS_LOCKFILES {
+        let lock_path = project_path.join(filename);
+        if !lock_path.exists() {
+            continue;
+        }
+
+        let content = fs::read_to_string(&lock_path)
+            .map_err(|error| anyhow!("Failed to read {}: {}", lock_path.display(), error))?;
+
+        if content.len() > MAX_JS_LOCKFILE_SIZE {
+            return Err(anyhow!("File {} is too large (>20MB)", lock_path.display()));
+        }
+
+        locked = match *filename {
+            "yarn.lock" => parse_yarn_lock(&content, &dependencies),
+            "package-lock.json" => parse_package_lock(&content),
+            _ => parse_pnpm_lock(&content),
+        };
+        lockfile = Some(lock_path);
+        break;
+    }
+
+    let package = |(name, requirement): (&String, &String)| TsPackage {
+        name: name.clone(),
+        requirement: requirement.clone(),
+        resolved: locked.get(name).cloned(),
+    };
+    scan.ts_client = Some(TsClientVersions {
+        package_json: path,
+        lockfile,
+        anchor: anchor.map(package),
+        web3: web3.map(package),
+    });
+
+    Ok(())
+}
+
+/// Record the manifest's MSRV as a lower bound on the Rust version. Members
+/// that inherit `rust-version` are attributed to the workspace root.
+fn record_rust_version(
+    scan: &mut DirectoryScan,
+    path: &Path,
+    manifest: &CargoManifest,
+    workspace_root: Option<&(PathBuf, CargoManifest)>,
+) {
+    let inherited = workspace_root
+        .filter(|_| manifest.inherits_rust_version)
+        .and_then(|(root_path, root_manifest)| {
+            Some((
+                root_path.as_path(),
+                root_manifest.workspace_rust_version.as_ref()?,
+            ))
+        });
+    let Some((declared_in, rust_version)) = manifest
+        .rust_version
+        .as_ref()
+        .or(manifest.workspace_rust_version.as_ref())
+        .map(|rust_version| (path, rust_version))
+        .or(inherited)
+    else {
+        return;
+    };
+    let Ok(requirement) = VersionReq::parse(&format!(">={}", rust_version.trim())) else {
+        return;
+    };
+
+    set_version(
+        scan,
+        VersionField::Rust,
+        VersionSourceKind::CargoRustVersion,
+        declared_in,
+        rust_version.trim().to_string(),
+        Some(requirement),
+        None,
+    );
+}
+
+/// Warn when a recognised crate comes from git at a rev or branch that cannot
+/// be mapped to a release.
+fn warn_about_unmapped_git_source(
+    scan: &mut DirectoryScan,
+    options: &ScanOptions,
+    path: &Path,
+    dependency: &ManifestDependency,
+) {
+    let Some(git) = &dependency.git else {
+        return;
+    };
+    if dependency.requirement.is_some()
+        || git.tag_version().is_some()
+        || !options
+            .recognized_crates
+            .iter()
+            .any(|recognized| recognized.matches(&dependency.name))
+    {
+        return;
+    }
+
+    let reference = git
+        .rev
+        .as_deref()
+        .map(|rev| format!("rev {rev}"))
+        .or_else(|| {
+            git.branch
+                .as_deref()
+                .map(|branch| format!("branch {branch}"))
+        })
+        .or_else(|| git.tag.as_deref().map(|tag| format!("tag {tag}")))
+        .unwrap_or_else(|| "the default branch".to_string());
+    let repository = git.repository.as_deref().unwrap_or(&git.url);
+
+    scan.warnings.push(format!(
+        "{} in {} is pinned to {reference} of {repository}, which cannot be mapped to a release.",
+        dependency.name,
+        path.display(),
+    ));
+}
+
+/// Find the manifest owning the workspace `project_path` belongs to: the
+/// project's own manifest when it declares `[workspace]`, otherwise the
+/// nearest ancestor manifest that does.
+fn find_workspace_root(
+    project_path: &Path,
+    manifest: &CargoManifest,
+) -> Result<Option<(PathBuf, CargoManifest)>> {
+    let start = project_path
+        .canonicalize()
+        .unwrap_or_else(|_| project_path.to_path_buf());
+
+    if manifest.is_workspace_root {
+        return Ok(Some((start.join("Cargo.toml"), manifest.clone())));
+    }
+
+    for dir in start.ancestors().skip(1) {
+        let path = dir.join("Cargo.toml");
+        if !path.exists() {
+            continue;
+        }
+
+        let candidate = parse_cargo_manifest(&read_cargo_toml(&path)?);
+        if candidate.is_workspace_root
+            && let Ok(relative) = start.strip_prefix(dir)
+            && is_workspace_member(&candidate, relative)
+        {
+            return Ok(Some((path, candidate)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether the package at `relative`, a path below the workspace root, is
+/// listed in `workspace.members` and not in `workspace.exclude`.
+fn is_workspace_member(root: &CargoManifest, relative: &Path) -> bool {
+    let excluded = root
+        .workspace_exclude
+        .iter()
+        .any(|excluded| relative.starts_with(excluded.trim_end_matches('/')));
+    let listed = root.workspace_members.iter().any(|member| {
+        let pattern: Vec<&str> = member
+            .trim_end_matches('/')
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        let components: Vec<&str> = relative
+            .components()
+            .filter_map(|component| component.as_os_str().to_str())
+            .collect();
+        pattern.len() == components.len()
+            && pattern
+                .iter()
+                .zip(&components)
+                .all(|(pattern, component)| glob_matches(pattern, component))
+    });
+
+    listed && !excluded
+}
+
+/// Match a single path component against a pattern that may contain one `*`.
+fn glob_matches(pattern: &str, component: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            component.len() >= prefix.len().saturating_add(suffix.len())
+                && component.starts_with(prefix)
+                && component.ends_with(suffix)
+        }
+        None => pattern == component,
+    }
+}
+
+fn read_cargo_toml(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
     if content.len() > MAX_TOML_FILE_SIZE {
         return Err(anyhow!("File {} is too large (>100KB)", path.display()));
     }
 
-    let parsed = parse_cargo_toml(&content);
-    if let Some(solana_version) = parsed.solana_version {
-        set_version(
-            versions,
-            sources,
-            VersionField::Solana,
-            VersionSourceKind::CargoToml,
-            &path,
-            solana_version,
-        );
-    }
-    if let Some(anchor_version) = parsed.anchor_version {
+    Ok(content)
+}
+
+/// Record the strongest signal per field. Signals inherited from a workspace
+/// root are attributed to the root manifest as well.
+fn record_dependency_signals(
+    scan: &mut DirectoryScan,
+    kind: VersionSourceKind,
+    path: &Path,
+    signals: &[DependencySignal],
+) {
+    for field in [VersionField::Solana, VersionField::Anchor] {
+        let Some(signal) = strongest_signal(signals, field) else {
+            continue;
+        };
+
         set_version(
-            versions,
-            sources,
-            VersionField::Anchor,
-            VersionSourceKind::CargoToml,
-            &path,
-            anchor_version,
+            scan,
+            field,
+            kind,
+            path,
+            signal.version.clone(),
+            signal.requirement.clone(),
+            Some(signal.origin.clone()),
         );
-    }
 
-    Ok(())
+        if let Some(root_path) = &signal.origin.inherited_from
+            && root_path != path
+        {
+            set_version(
+                scan,
+                field,
+                kind,
+                root_path,
+                signal.version.clone(),
+                signal.requirement.clone(),
+                Some(DependencyOrigin {
+                    table: Some(DependencyTable::Workspace),
+                    target: None,
+                    inherited_from: None,
+                    ..signal.origin.clone()
+                }),
+            );
+        }
+    }
 }
 
 fn set_version(
-    versions: &mut ProjectVersions,
-    sources: &mut Vec<VersionSource>,
+    scan: &mut DirectoryScan,
     field: VersionField,
     kind: VersionSourceKind,
     path: &Path,
     value: String,
+    requirement: Option<VersionReq>,
+    dependency: Option<DependencyOrigin>,
 ) {
-    let target = match field {
-        VersionField::Rust => &mut versions.rust_version,
-        VersionField::Solana => &mut versions.solana_version,
-        VersionField::Anchor => &mut versions.anchor_version,
+    let versions = &mut scan.versions;
+    let (target, target_requirement) = match field {
+        VersionField::Rust => (&mut versions.rust_version, &mut versions.requirements.rust),
+        VersionField::Solana => (
+            &mut versions.solana_version,
+            &mut versions.requirements.solana,
+        ),
+        VersionField::Anchor => (
+            &mut versions.anchor_version,
+            &mut versions.requirements.anchor,
+        ),
     };
 
-    if target.is_none() || target.as_ref().is_some_and(|current| current == "*") {
+    let current_precedence = scan
+        .sources
+        .iter()
+        .filter(|source| source.field == field)
+        .map(|source| source.kind.precedence())
+        .max();
+
+    if target.as_ref().is_none_or(|current| current == "*")
+        || current_precedence.is_some_and(|current| kind.precedence() > current)
+    {
         *target = Some(value.clone());
-        sources.push(VersionSource {
-            field,
-            kind,
-            path: path.to_path_buf(),
-            value,
-        });
+        target_requirement.clone_from(&requirement);
+    }
+
+    scan.sources.push(VersionSource {
+        field,
+        kind,
+        path: path.to_path_buf(),
+        value,
+        requirement,
+        dependency,
+    });
+}
+
+fn dedup_sources(sources: &mut Vec<VersionSource>) {
+    let mut seen = Vec::with_capacity(sources.len());
+    sources.retain(|source| {
+        if seen.contains(source) {
+            false
+        } else {
+            seen.push(source.clone());
+            true
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "anchor-version-detector-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_workspace_inherited_dependency_is_attributed_to_member_and_root() {
+        let root = scratch_dir("workspace-inheritance");
+        let member = root.join("programs").join("vault");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"programs/*\"]\n\n[workspace.dependencies]\nanchor-lang = \"0.30.1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"vault\"\n\n[dependencies]\nanchor-lang = { workspace = true }\n",
+        )
+        .unwrap();
+
+        let DirectoryScan {
+            versions, sources, ..
+        } = detect_versions_in_dir(&member).unwrap();
+        let root_manifest = root.canonicalize().unwrap().join("Cargo.toml");
+
+        assert_eq!(versions.anchor_version.as_deref(), Some("0.30.1"));
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].path, member.join("Cargo.toml"));
+        assert_eq!(
+            sources[0]
+                .dependency
+                .as_ref()
+                .and_then(|origin| origin.inherited_from.as_deref()),
+            Some(root_manifest.as_path())
+        );
+        assert_eq!(sources[1].path, root_manifest);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unmapped_git_rev_produces_warning() {
+        let root = scratch_dir("git-rev");
+        fs::write(
+            root.join("Cargo.toml"),
+            "[dependencies]\nanchor-lang = { git = \"https://github.com/coral-xyz/anchor\", rev = \"4f1a2b3\" }\nsolana-program = \"1.18.17\"\n",
+        )
+        .unwrap();
+
+        let scan = detect_versions_in_dir(&root).unwrap();
+
+        assert_eq!(scan.versions.anchor_version.as_deref(), Some("*"));
+        assert_eq!(scan.warnings.len(), 1);
+        assert!(scan.warnings[0].contains("rev 4f1a2b3 of coral-xyz/anchor"));
+        let git = scan
+            .sources
+            .iter()
+            .find(|source| source.field == VersionField::Anchor)
+            .and_then(|source| source.dependency.as_ref()?.git.as_ref())
+            .unwrap();
+        assert_eq!(git.repository.as_deref(), Some("coral-xyz/anchor"));
+        assert_eq!(git.rev.as_deref(), Some("4f1a2b3"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_inherited_rust_version_is_a_lower_bound_from_workspace_root() {
+        let root = scratch_dir("rust-version");
+        let member = root.join("programs").join("vault");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"programs/*\"]\n\n[workspace.package]\nrust-version = \"1.79\"\n",
+        )
+        .unwrap();
+        fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"vault\"\nrust-version.workspace = true\n\n[dependencies]\nanchor-lang = \"0.30.1\"\n",
+        )
+        .unwrap();
+
+        let scan = detect_versions_in_dir(&member).unwrap();
+        let source = scan
+            .sources
+            .iter()
+            .find(|source| source.kind == VersionSourceKind::CargoRustVersion)
+            .unwrap();
+
+        assert_eq!(scan.versions.rust_version.as_deref(), Some("1.79"));
+        assert_eq!(
+            scan.versions.requirements.rust,
+            Some(VersionReq::parse(">=1.79").unwrap())
+        );
+        assert_eq!(source.path, root.canonicalize().unwrap().join("Cargo.toml"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unsatisfiable_msrv_is_reported_once() {
+        let root = scratch_dir("msrv-once");
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"vault\"\nrust-version = \"1.90\"\n\n[dependencies]\nanchor-lang = \"0.30.1\"\n",
+        )
+        .unwrap();
+
+        let report = detect_versions_recursive(&root, &ScanOptions::default()).unwrap();
+        let rust_warnings: Vec<_> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.contains("1.90"))
+            .collect();
+
+        assert_eq!(rust_warnings.len(), 1, "{rust_warnings:?}");
+        assert!(rust_warnings[0].starts_with("No known compatibility rule satisfies all of"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_overridden_anchorversion_pin_is_not_solved_again() {
+        let root = scratch_dir("solver-anchorversion");
+        fs::write(
+            root.join("Anchor.toml"),
+            "[toolchain]\nanchor_version = \"0.30.1\"\n",
+        )
+        .unwrap();
+        fs::write(root.join(".anchorversion"), "0.29.0\n").unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[dependencies]\nanchor-lang = \"0.30.1\"\n",
+        )
+        .unwrap();
+
+        let report = detect_versions_recursive(&root, &ScanOptions::default()).unwrap();
+        assert!(matches!(report.solution, ConstraintSolution::Feasible(_)));
+        let mentions: Vec<_> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.contains("0.29.0"))
+            .collect();
+        assert_eq!(mentions.len(), 1, "{mentions:?}");
+        assert!(mentions[0].contains("using the Anchor.toml version"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_stale_idl_is_not_solved_against_anchor_lang() {
+        let root = scratch_dir("solver-idl");
+        fs::create_dir_all(root.join("idl")).unwrap();
+        fs::write(
+            root.join("idl").join("counter.json"),
+            r#"{"version": "0.1.0", "name": "counter", "instructions": []}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[dependencies]\nanchor-lang = \"0.30.1\"\n",
+        )
+        .unwrap();
+
+        let report = detect_versions_recursive(&root, &ScanOptions::default()).unwrap();
+        assert!(matches!(report.solution, ConstraintSolution::Feasible(_)));
+        let mentions: Vec<_> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.contains("idl"))
+            .collect();
+        assert_eq!(mentions.len(), 1, "{mentions:?}");
+        assert!(mentions[0].contains("uses the pre-0.30 legacy IDL format"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rust_version_is_not_inherited_outside_workspace_members() {
+        let root = scratch_dir("rust-version-members");
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"programs/*\"]\nexclude = [\"programs/legacy\"]\n\n[workspace.package]\nrust-version = \"1.79\"\n",
+        )
+        .unwrap();
+        for member in ["programs/legacy", "tools/cli"] {
+            let member = root.join(member);
+            fs::create_dir_all(&member).unwrap();
+            fs::write(
+                member.join("Cargo.toml"),
+                "[package]\nname = \"member\"\nrust-version.workspace = true\n\n[dependencies]\nanchor-lang = \"0.30.1\"\n",
+            )
+            .unwrap();
+
+            let scan = detect_versions_in_dir(&member).unwrap();
+            assert_eq!(scan.versions.rust_version, None);
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_anchor_toml_wins_over_conflicting_anchorversion() {
+        let root = scratch_dir("anchorversion");
+        fs::write(
+            root.join("Anchor.toml"),
+            "[toolchain]\nanchor_version = \"0.30.1\"\n",
+        )
+        .unwrap();
+        fs::write(root.join(".anchorversion"), "0.29.0\n").unwrap();
+
+        let scan = detect_versions_in_dir(&root).unwrap();
+
+        assert_eq!(scan.versions.anchor_version.as_deref(), Some("0.30.1"));
+        assert!(scan.sources.iter().any(|source| {
+            source.kind == VersionSourceKind::AnchorVersionFile && source.requirement.is_none()
+        }));
+        assert_eq!(scan.warnings.len(), 1);
+        assert!(scan.warnings[0].contains("pins Anchor 0.29.0"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_idl_format_is_a_fallback_and_checked_against_anchor_lang() {
+        let root = scratch_dir("idl");
+        fs::create_dir_all(root.join("target").join("idl")).unwrap();
+        fs::write(
+            root.join("target").join("idl").join("counter.json"),
+            r#"{"address": "Fg6P", "metadata": {"name": "counter", "version": "0.1.0", "spec": "0.1.0"}, "instructions": []}"#,
+        )
+        .unwrap();
+
+        let report = detect_versions_recursive(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(report.detected.anchor_version, None);
+        assert_eq!(
+            report.detected.requirements.anchor,
+            Some(VersionReq::parse(">=0.30.0").unwrap())
+        );
+        let latest_anchor = crate::latest_compatible_rule().anchor.clone();
+        assert_eq!(
+            report.resolved.anchor_version.as_deref(),
+            Some(latest_anchor.as_str())
+        );
+        assert_eq!(
+            report.compatibility.matched_rule.map(|rule| rule.anchor),
+            Some(latest_anchor)
+        );
+
+        fs::write(
+            root.join("Cargo.toml"),
+            "[dependencies]\nanchor-lang = \"0.29.0\"\n",
+        )
+        .unwrap();
+        let report = detect_versions_recursive(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(report.detected.anchor_version.as_deref(), Some("0.29.0"));
+        assert!(
+            report
+                .warnings
+                .iter()
+                .any(|warning| warning.contains("uses the 0.30+ IDL format"))
+        );
+        assert!(report.sources.iter().any(|source| {
+            source.kind == VersionSourceKind::Idl
+                && source.value == ">=0.30.0"
+                && source.requirement.is_none()
+        }));
+        assert!(matches!(report.solution, ConstraintSolution::Feasible(_)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_program_binary_versions_are_read_from_target_deploy() {
+        let root = scratch_dir("program-binary");
+        assert!(detect_versions_in_binary(&root, &ScanOptions::default()).is_err());
+
+        let deploy = root.join("target").join("deploy");
+        fs::create_dir_all(&deploy).unwrap();
+        fs::write(
+            deploy.join("counter.so"),
+            crate::elf::tests::elf_with_sections(&[
+                (".comment", b"rustc version 1.75.0-dev\0"),
+                (
+                    ".rodata",
+                    b"/src/anchor-lang-0.29.0/src/lib.rs\0/src/solana-program-1.17.3/src/lib.rs\0",
+                ),
+            ]),
+        )
+        .unwrap();
+        fs::write(deploy.join("counter-keypair.json"), "[]").unwrap();
+
+        let report = detect_versions_in_binary(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(report.detected.anchor_version.as_deref(), Some("0.29.0"));
+        assert_eq!(report.detected.solana_version.as_deref(), Some("1.17.3"));
+        assert_eq!(report.detected.rust_version, None);
+        assert_eq!(report.program_binaries.len(), 1);
+        assert!(report.sources.iter().any(|source| {
+            source.field == VersionField::Rust
+                && source.kind == VersionSourceKind::ProgramBinary
+                && source.value == "1.75.0-dev"
+        }));
+        assert_eq!(
+            report
+                .source_for(VersionField::Anchor)
+                .map(|source| source.kind),
+            Some(VersionSourceKind::ProgramBinary)
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_ci_pins_fill_gaps_and_report_disagreements() {
+        let root = scratch_dir("text-signals");
+        let workflows = root.join(".github").join("workflows");
+        fs::create_dir_all(&workflows).unwrap();
+        fs::write(
+            workflows.join("ci.yml"),
+            "env:\n  SOLANA_VERSION: 1.17.0\n  ANCHOR_VERSION: 0.29.0\nrun: cargo build-sbf --tools-version v1.43\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("programs").join("counter")).unwrap();
+        fs::write(
+            root.join("programs").join("counter").join("Cargo.toml"),
+            "[dependencies]\nsolana-program = \"1.18.17\"\n",
+        )
+        .unwrap();
+
+        let report = detect_versions_recursive(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(report.detected.solana_version.as_deref(), Some("1.18.17"));
+        assert_eq!(report.detected.anchor_version.as_deref(), Some("0.29.0"));
+        assert_eq!(
+            report
+                .source_for(VersionField::Anchor)
+                .map(|source| source.kind),
+            Some(VersionSourceKind::CiWorkflow)
+        );
+        assert_eq!(report.text_signals.len(), 3);
+        assert_eq!(
+            report.detected.platform_tools_version.as_deref(),
+            Some("v1.43")
+        );
+        assert!(report.warnings.iter().any(|warning| {
+            warning.contains("ci.yml line 2 pins Solana 1.17.0")
+                && warning.contains("detected version 1.18.17")
+        }));
+
+        fs::remove_dir_all(&root).unwrap();
     }
 }
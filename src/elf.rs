@@ -0,0 +1,295 @@
+use anyhow::{Result, anyhow};
+use semver::Version;
+
+use crate::parse::exact_version_requirement;
+use crate::types::{
+    BinaryCrateVersion, DependencyOrigin, DependencySignal, ProgramBinary, RecognizedCrate,
+};
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const ELF_CLASS_64: u8 = 2;
+const ELF_DATA_LITTLE_ENDIAN: u8 = 1;
+const SECTION_HEADER_SIZE: u16 = 64;
+const SECTION_TYPE_NOBITS: u32 = 8;
+const MIN_STRING_LENGTH: usize = 4;
+
+/// A named section of an ELF file and its contents.
+struct Section<'a> {
+    name: &'a str,
+    data: &'a [u8],
+}
+
+/// Inspect a compiled SBF program for the compiler that built it and the
+/// versions of recognised crates linked into it.
+///
+/// The compiler is read from the `.comment` section. Crate versions come from
+/// the build paths that panic locations leave in `.rodata`, such as
+/// `.../anchor-lang-0.30.1/src/lib.rs`.
+///
+/// # Errors
+///
+/// Returns an error when the input is not a little-endian 64-bit ELF file or
+/// its section table is malformed.
+pub fn parse_program_binary(bytes: &[u8], crates: &[RecognizedCrate]) -> Result<ProgramBinary> {
+    let sections = elf_sections(bytes)?;
+
+    let compiler_comments: Vec<String> = sections
+        .iter()
+        .filter(|section| section.name == ".comment")
+        .flat_map(|section| section.data.split(|byte| *byte == 0))
+        .filter_map(|comment| std::str::from_utf8(comment).ok())
+        .map(str::trim)
+        .filter(|comment| !comment.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let rustc_version = compiler_comments.iter().find_map(|comment| {
+        let version = comment.split("rustc version ").nth(1)?;
+        version.split_whitespace().next().map(str::to_string)
+    });
+    let platform_tools_version = compiler_comments.iter().find_map(|comment| {
+        let version = comment.split("platform-tools ").nth(1)?;
+        version
+            .split_whitespace()
+            .next()
+            .filter(|version| version.starts_with('v'))
+            .map(str::to_string)
+    });
+
+    let mut crate_versions: Vec<BinaryCrateVersion> = sections
+        .iter()
+        .filter(|section| section.name.starts_with(".rodata"))
+        .flat_map(|section| printable_strings(section.data))
+        .flat_map(|text| text.split(['/', '\\']).collect::<Vec<_>>())
+        .filter_map(crate_version_from_path_segment)
+        .filter(|found| crates.iter().any(|known| known.matches(&found.name)))
+        .collect();
+    crate_versions.sort_by(|left, right| left.name.cmp(&right.name));
+    crate_versions.dedup();
+
+    Ok(ProgramBinary {
+        rustc_version,
+        platform_tools_version,
+        compiler_comments,
+        crate_versions,
+    })
+}
+
+/// Turn the crate versions found in a binary into dependency signals.
+#[must_use]
+pub fn program_binary_signals(
+    program: &ProgramBinary,
+    crates: &[RecognizedCrate],
+) -> Vec<DependencySignal> {
+    program
+        .crate_versions
+        .iter()
+        .filter_map(|found| {
+            let recognized = crates.iter().find(|known| known.matches(&found.name))?;
+            Some(DependencySignal {
+                field: recognized.field,
+                weight: recognized.weight,
+                version: found.version.clone(),
+                requirement: exact_version_requirement(&found.version),
+                origin: DependencyOrigin {
+                    crate_name: found.name.clone(),
+                    alias: None,
+                    table: None,
+                    target: None,
+                    inherited_from: None,
+                    git: None,
+                },
+            })
+        })
+        .collect()
+}
+
+fn elf_sections(bytes: &[u8]) -> Result<Vec<Section<'_>>> {
+    let malformed = || anyhow!("Malformed ELF section table");
+
+    if bytes.get(..4) != Some(ELF_MAGIC) {
+        return Err(anyhow!("Not an ELF file"));
+    }
+    if bytes.get(4) != Some(&ELF_CLASS_64) || bytes.get(5) != Some(&ELF_DATA_LITTLE_ENDIAN) {
+        return Err(anyhow!("Only little-endian 64-bit ELF files are supported"));
+    }
+
+    let table_offset = usize::try_from(read_u64(bytes, 0x28).ok_or_else(malformed)?)?;
+    let entry_size = read_u16(bytes, 0x3A).ok_or_else(malformed)?;
+    let entry_count = read_u16(bytes, 0x3C).ok_or_else(malformed)?;
+    let names_index = read_u16(bytes, 0x3E).ok_or_else(malformed)?;
+    if entry_size < SECTION_HEADER_SIZE {
+        return Err(malformed());
+    }
+
+    let mut headers = Vec::with_capacity(usize::from(entry_count));
+    for index in 0..entry_count {
+        let start = usize::from(index)
+            .checked_mul(usize::from(entry_size))
+            .and_then(|offset| offset.checked_add(table_offset))
+            .ok_or_else(malformed)?;
+        let name = read_u32(bytes, start).ok_or_else(malformed)?;
+        let kind =
+            read_u32(bytes, start.checked_add(4).ok_or_else(malformed)?).ok_or_else(malformed)?;
+        let offset =
+            read_u64(bytes, start.checked_add(24).ok_or_else(malformed)?).ok_or_else(malformed)?;
+        let size =
+            read_u64(bytes, start.checked_add(32).ok_or_else(malformed)?).ok_or_else(malformed)?;
+
+        let data = if kind == SECTION_TYPE_NOBITS {
+            &[][..]
+        } else {
+            let offset = usize::try_from(offset)?;
+            let end = offset
+                .checked_add(usize::try_from(size)?)
+                .ok_or_else(malformed)?;
+            bytes.get(offset..end).ok_or_else(malformed)?
+        };
+        headers.push((usize::try_from(name)?, data));
+    }
+
+    let names = headers
+        .get(usize::from(names_index))
+        .map(|(_, data)| *data)
+        .ok_or_else(malformed)?;
+
+    Ok(headers
+        .into_iter()
+        .map(|(name_offset, data)| {
+            let name = names
+                .get(name_offset..)
+                .and_then(|rest| rest.split(|byte| *byte == 0).next())
+                .and_then(|name| std::str::from_utf8(name).ok())
+                .unwrap_or_default();
+            Section { name, data }
+        })
+        .collect())
+}
+
+fn read_bytes<const N: usize>(bytes: &[u8], offset: usize) -> Option<[u8; N]> {
+    bytes.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    read_bytes(bytes, offset).map(u16::from_le_bytes)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    read_bytes(bytes, offset).map(u32::from_le_bytes)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    read_bytes(bytes, offset).map(u64::from_le_bytes)
+}
+
+/// Runs of printable ASCII at least [`MIN_STRING_LENGTH`] bytes long.
+fn printable_strings(data: &[u8]) -> impl Iterator<Item = &str> {
+    data.split(|byte| !(0x20..=0x7e).contains(byte))
+        .filter(|run| run.len() >= MIN_STRING_LENGTH)
+        .filter_map(|run| std::str::from_utf8(run).ok())
+}
+
+/// Split a path segment such as `anchor-lang-0.30.1` into crate name and
+/// version.
+fn crate_version_from_path_segment(segment: &str) -> Option<BinaryCrateVersion> {
+    segment.match_indices('-').find_map(|(index, _)| {
+        let name = segment.get(..index)?;
+        let version = segment.get(index..)?.strip_prefix('-')?;
+        Version::parse(version).ok()?;
+        Some(BinaryCrateVersion {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::crates::recognized_crates;
+
+    /// Build a minimal ELF64 file with the given sections.
+    pub fn elf_with_sections(sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut names = vec![0_u8];
+        let mut name_offsets = Vec::new();
+        for (name, _) in sections {
+            name_offsets.push(u32::try_from(names.len()).unwrap());
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        let names_offset = u32::try_from(names.len()).unwrap();
+        names.extend_from_slice(b".shstrtab\0");
+
+        let mut bytes = vec![0_u8; 64];
+        bytes[..4].copy_from_slice(ELF_MAGIC);
+        bytes[4] = ELF_CLASS_64;
+        bytes[5] = ELF_DATA_LITTLE_ENDIAN;
+
+        let mut layout = Vec::new();
+        for ((_, data), name) in sections.iter().zip(&name_offsets) {
+            layout.push((*name, bytes.len(), data.len()));
+            bytes.extend_from_slice(data);
+        }
+        layout.push((names_offset, bytes.len(), names.len()));
+        bytes.extend_from_slice(&names);
+
+        let table_offset = bytes.len();
+        bytes.extend_from_slice(&[0_u8; 64]);
+        for (name, offset, size) in &layout {
+            let mut header = [0_u8; 64];
+            header[..4].copy_from_slice(&name.to_le_bytes());
+            header[4..8].copy_from_slice(&1_u32.to_le_bytes());
+            header[24..32].copy_from_slice(&u64::try_from(*offset).unwrap().to_le_bytes());
+            header[32..40].copy_from_slice(&u64::try_from(*size).unwrap().to_le_bytes());
+            bytes.extend_from_slice(&header);
+        }
+
+        let names_index = u16::try_from(layout.len()).unwrap();
+        let count = names_index.checked_add(1).unwrap();
+        bytes[0x28..0x30].copy_from_slice(&u64::try_from(table_offset).unwrap().to_le_bytes());
+        bytes[0x3A..0x3C].copy_from_slice(&64_u16.to_le_bytes());
+        bytes[0x3C..0x3E].copy_from_slice(&count.to_le_bytes());
+        bytes[0x3E..0x40].copy_from_slice(&names_index.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_parse_program_binary_reads_compiler_and_crates() {
+        let binary = elf_with_sections(&[
+            (
+                ".comment",
+                b"Linker: LLD 17.0.6\0rustc version 1.75.0-dev\0",
+            ),
+            (
+                ".rodata",
+                b"\x01/home/runner/.cargo/registry/src/index.crates.io-6f17d22bba15001f/anchor-lang-0.30.1/src/lib.rs\0\x02/home/runner/.cargo/registry/src/index.crates.io-6f17d22bba15001f/solana-program-1.18.17/src/entrypoint.rs\0bn-0.1.0\0",
+            ),
+        ]);
+
+        let program = parse_program_binary(&binary, recognized_crates()).unwrap();
+
+        assert_eq!(program.rustc_version.as_deref(), Some("1.75.0-dev"));
+        assert_eq!(program.compiler_comments.len(), 2);
+        assert_eq!(
+            program.crate_versions,
+            vec![
+                BinaryCrateVersion {
+                    name: "anchor-lang".to_string(),
+                    version: "0.30.1".to_string(),
+                },
+                BinaryCrateVersion {
+                    name: "solana-program".to_string(),
+                    version: "1.18.17".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_program_binary_rejects_non_elf_input() {
+        assert!(parse_program_binary(b"not an elf", recognized_crates()).is_err());
+        let mut truncated = elf_with_sections(&[(".comment", b"rustc version 1.75.0\0")]);
+        truncated.truncate(80);
+        assert!(parse_program_binary(&truncated, recognized_crates()).is_err());
+    }
+}
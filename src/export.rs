@@ -0,0 +1,100 @@
+use semver::Version;
+
+use crate::parse::{clean_version, exact_version_requirement};
+use crate::types::{CompatibilityRule, ProjectVersions, VersionField};
+
+/// Render `versions` as an asdf/mise `.tool-versions` file.
+///
+/// Pass `DetectionReport::resolved` and the matched rule from
+/// `DetectionReport::compatibility` to pin the whole toolchain in one step.
+/// Only full `major.minor.patch` versions are written, as asdf and mise
+/// cannot install partial versions or ranges. A partial version such as
+/// `1.18` is replaced by the rule's version when that falls within it, and
+/// fields that still have no full version, such as a `nightly` toolchain, are
+/// left out.
+#[must_use]
+pub fn format_tool_versions(
+    versions: &ProjectVersions,
+    rule: Option<&CompatibilityRule>,
+) -> String {
+    [
+        ("rust", VersionField::Rust, &versions.rust_version),
+        ("solana", VersionField::Solana, &versions.solana_version),
+        ("anchor", VersionField::Anchor, &versions.anchor_version),
+    ]
+    .into_iter()
+    .filter_map(|(tool, field, version)| {
+        let version = installable_version(versions, field, version.as_deref()?, rule)?;
+        Some(format!("{tool} {version}\n"))
+    })
+    .collect()
+}
+
+fn installable_version(
+    versions: &ProjectVersions,
+    field: VersionField,
+    version: &str,
+    rule: Option<&CompatibilityRule>,
+) -> Option<String> {
+    let cleaned = clean_version(version.trim());
+    if Version::parse(&cleaned).is_ok() {
+        return Some(cleaned);
+    }
+
+    let rule_version = Version::parse(rule?.version(field)).ok()?;
+    let requirement = versions
+        .requirements
+        .get(field)
+        .cloned()
+        .or_else(|| exact_version_requirement(&cleaned))?;
+    requirement
+        .matches(&rule_version)
+        .then(|| rule_version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compatibility::find_rule_by_anchor;
+    use crate::parse::{parse_tool_versions, parse_version_requirement};
+    use crate::types::VersionRequirements;
+
+    #[test]
+    fn test_format_tool_versions_round_trips() {
+        let versions = ProjectVersions {
+            rust_version: Some("1.76.0".to_string()),
+            solana_version: Some("^1.18.17".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let content = format_tool_versions(&versions, None);
+        assert_eq!(content, "rust 1.76.0\nsolana 1.18.17\nanchor 0.30.1\n");
+
+        let parsed = parse_tool_versions(&content);
+        assert_eq!(parsed.solana_version.as_deref(), Some("1.18.17"));
+        assert_eq!(parsed.anchor_version.as_deref(), Some("0.30.1"));
+    }
+
+    #[test]
+    fn test_format_tool_versions_writes_only_installable_versions() {
+        let versions = ProjectVersions {
+            rust_version: Some("nightly-2024-05-01".to_string()),
+            solana_version: Some("1.18".to_string()),
+            anchor_version: Some(">=0.30.0".to_string()),
+            requirements: VersionRequirements {
+                solana: parse_version_requirement("^1.18"),
+                anchor: parse_version_requirement(">=0.30.0"),
+                ..VersionRequirements::default()
+            },
+            ..ProjectVersions::default()
+        };
+        let rule = find_rule_by_anchor("0.30.1");
+
+        assert_eq!(
+            format_tool_versions(&versions, rule),
+            "solana 1.18.17\nanchor 0.30.1\n"
+        );
+        assert_eq!(format_tool_versions(&versions, None), "");
+    }
+}
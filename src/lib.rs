@@ -1,20 +1,54 @@
 pub mod compatibility;
+pub mod crates;
 pub mod detect;
+pub mod elf;
 pub mod env;
+pub mod export;
+pub mod matrix;
 pub mod parse;
+pub mod rust_releases;
+pub mod solver;
+pub mod text_signals;
 pub mod types;
+pub mod verify;
 
 pub use compatibility::{
-    assess_versions, compatibility_rules, find_rule_by_anchor, find_rule_by_solana,
-    latest_compatible_rule, resolve_versions,
+    assess_versions, check_rust_version, check_ts_client, compatibility_rules, find_platform_tools,
+    find_rule_by_anchor, find_rule_by_solana, latest_compatible_rule, match_rule_by_anchor,
+    match_rule_by_anchor_requirement, match_rule_by_solana, match_rule_by_solana_requirement,
+    platform_tools_for_solana, platform_tools_releases, resolve_versions,
 };
-pub use detect::{detect_versions_in_dir, detect_versions_recursive};
+pub use crates::{known_repositories, recognized_crates};
+pub use detect::{
+    detect_versions_in_binary, detect_versions_in_dir, detect_versions_in_dir_with,
+    detect_versions_recursive,
+};
+pub use elf::{parse_program_binary, program_binary_signals};
 pub use env::{detect_current_environment, get_agave_version, get_avm_version, get_rustc_version};
+pub use export::format_tool_versions;
 pub use parse::{
-    clean_version, parse_anchor_toml, parse_cargo_toml, parse_rust_toolchain, parse_semver_range,
+    clean_version, dependency_signals, exact_version_requirement, parse_anchor_config,
+    parse_anchor_toml, parse_anchor_version_file, parse_cargo_dependencies, parse_cargo_lock,
+    parse_cargo_lock_dependencies, parse_cargo_manifest, parse_cargo_toml, parse_git_source,
+    parse_mise_toml, parse_rust_toolchain, parse_rust_toolchain_spec, parse_semver_range,
+    parse_tool_versions, parse_version_requirement, parse_yarn_lock, requirement_lower_bound,
+    requirement_upper_bound, requirements_overlap, strongest_signal,
 };
+pub use rust_releases::{rust_releases, stable_equivalent};
+pub use solver::{constraints_from_sources, solve_constraints};
+pub use text_signals::{extract_text_signals, text_signal_kind, text_signal_patterns};
 pub use types::{
-    CompatibilityAssessment, CompatibilityReason, CompatibilityRule, Confidence,
-    CurrentEnvironment, DetectionReport, InferredFields, ProjectVersions, ScanOptions,
-    VersionField, VersionSource, VersionSourceKind,
+    AnchorConfig, AnchorFeatures, AnchorGenesisProgram, AnchorProgram, AnchorProvider,
+    AnchorTestConfig, AnchorToolchain, AnchorValidatorAccount, AnchorValidatorConfig,
+    AnchorWorkspace, BinaryCrateVersion, BoundsStatus, CandidateMatch, CargoManifest,
+    CompatibilityAssessment, CompatibilityMatrix, CompatibilityReason, CompatibilityRule,
+    Confidence, ConstraintSolution, CurrentEnvironment, DependencyOrigin, DependencySignal,
+    DependencyTable, DetectionReport, DirectoryScan, FallbackPolicy, FeasibleCombination,
+    GitSource, Inconsistency, InferredFields, KnownRepository, ManifestDependency, MatchKind,
+    PinnedTool, PlatformToolsRelease, ProgramBinary, ProjectVersions, RecognizedCrate,
+    RepositoryKind, RuleMatch, RustRelease, RustToolchainSpec, RustVersionMapping, ScanOptions,
+    TestedRanges, TextSignal, TextSignalPattern, ToolchainChannel, TsClientVersions, TsPackage,
+    UnsatisfiableCore, Verification, VersionBounds, VersionCheck, VersionConstraint,
+    VersionDistance, VersionField, VersionRequirements, VersionSource, VersionSourceKind,
 };
+pub use verify::verify_versions;
@@ -1,23 +1,75 @@
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{Result, anyhow};
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use anchor_version_detector::{
-    clean_version, detect_current_environment, detect_versions_recursive, DetectionReport,
-    ScanOptions,
+    CompatibilityMatrix, DetectionReport, ScanOptions, VersionSourceKind, clean_version,
+    detect_current_environment, detect_versions_in_binary, detect_versions_recursive,
+    format_tool_versions,
 };
 
-const EXPECTED_ARGS_COUNT: usize = 2;
+const WRITE_TOOL_VERSIONS_FLAG: &str = "--write-tool-versions";
+const MATRIX_FLAG: &str = "--matrix";
 const UNKNOWN_VERSION: &str = "Unknown";
 const UNKNOWN_ANCHOR_VERSION: &str = "Unknown (may not be an Anchor project)";
 
+struct CliArgs {
+    project_path: PathBuf,
+    write_tool_versions: bool,
+    matrix_path: Option<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Option<CliArgs> {
+    let mut project_path = None;
+    let mut write_tool_versions = false;
+    let mut matrix_path = None;
+
+    let mut remaining = args.iter().skip(1);
+    while let Some(arg) = remaining.next() {
+        if arg == WRITE_TOOL_VERSIONS_FLAG {
+            write_tool_versions = true;
+        } else if arg == MATRIX_FLAG {
+            matrix_path = Some(PathBuf::from(remaining.next()?));
+        } else if project_path.is_none() {
+            project_path = Some(PathBuf::from(arg));
+        } else {
+            return None;
+        }
+    }
+
+    Some(CliArgs {
+        project_path: project_path?,
+        write_tool_versions,
+        matrix_path,
+    })
+}
+
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
-    if args.len() != EXPECTED_ARGS_COUNT {
-        println!("Usage: {} <project_directory>", args[0]);
+    let Some(cli) = parse_args(&args) else {
+        println!(
+            "Usage: {} <project_directory> [{WRITE_TOOL_VERSIONS_FLAG}] [{MATRIX_FLAG} <file>]",
+            args[0]
+        );
         return Ok(());
+    };
+    let project_path = cli.project_path.as_path();
+
+    let mut matrix = CompatibilityMatrix::built_in().clone();
+    if let Some(matrix_path) = &cli.matrix_path {
+        matrix.merge(CompatibilityMatrix::load(matrix_path)?);
     }
+    let options = ScanOptions {
+        matrix: Cow::Owned(matrix),
+        ..ScanOptions::default()
+    };
 
-    let report = detect_versions_recursive(Path::new(&args[1]), &ScanOptions::default())?;
+    let report = if project_path.is_file() {
+        detect_versions_in_binary(project_path, &options)?
+    } else {
+        detect_versions_recursive(project_path, &options)?
+    };
     let current_env = detect_current_environment();
 
     println!("Detected/Inferred Versions:");
@@ -35,10 +87,7 @@ fn main() -> Result<()> {
 
     println!("\nTo work with this project, configure your environment as follows:");
     println!("```");
-    if let Some(rust_version) = &report.resolved.rust_version {
-        println!("rustup default {}", clean_version(rust_version));
-        println!("rustup component add rust-analyzer");
-    }
+    print_rustup_commands(&report);
     if let Some(solana_version) = &report.resolved.solana_version {
         println!("agave-install init {}", clean_version(solana_version));
     }
@@ -47,19 +96,74 @@ fn main() -> Result<()> {
     }
     println!("```");
 
+    if cli.write_tool_versions {
+        write_tool_versions_file(project_path, &report)?;
+    }
+
+    Ok(())
+}
+
+fn write_tool_versions_file(project_path: &Path, report: &DetectionReport) -> Result<()> {
+    let path = project_path.join(".tool-versions");
+    if path.exists() {
+        println!("\n{} already exists; leaving it unchanged.", path.display());
+        return Ok(());
+    }
+
+    fs::write(
+        &path,
+        format_tool_versions(&report.resolved, report.compatibility.matched_rule.as_ref()),
+    )
+    .map_err(|error| anyhow!("Failed to write {}: {}", path.display(), error))?;
+    println!("\nWrote {}", path.display());
     Ok(())
 }
 
+fn print_rustup_commands(report: &DetectionReport) {
+    let Some(spec) = &report.rust_toolchain else {
+        if let Some(rust_version) = &report.resolved.rust_version {
+            println!("rustup default {}", clean_version(rust_version));
+            println!("rustup component add rust-analyzer");
+        }
+        return;
+    };
+
+    let Some(toolchain) = spec.toolchain_name() else {
+        if let Some(path) = &spec.path {
+            println!("rustup toolchain link project {}", path.display());
+            println!("rustup default project");
+        }
+        return;
+    };
+
+    print!("rustup toolchain install {toolchain}");
+    if let Some(profile) = &spec.profile {
+        print!(" --profile {profile}");
+    }
+    if !spec.components.is_empty() {
+        print!(" --component {}", spec.components.join(","));
+    }
+    if !spec.targets.is_empty() {
+        print!(" --target {}", spec.targets.join(","));
+    }
+    println!();
+    println!("rustup default {toolchain}");
+}
+
 fn print_detected_versions(report: &DetectionReport) {
     let rust_source = report
-        .sources
-        .iter()
-        .find(|source| matches!(source.field, anchor_version_detector::VersionField::Rust))
-        .map(|source| format!("(from {})", source.path.display()))
+        .source_for(anchor_version_detector::VersionField::Rust)
+        .map(|source| {
+            if source.kind == VersionSourceKind::CargoRustVersion {
+                format!("(MSRV {} from {})", source.value, source.path.display())
+            } else {
+                format!("(from {})", source.path.display())
+            }
+        })
         .unwrap_or_default();
 
     println!(
-        "Rust: {} {}",
+        "Host Rust (tests, IDL build): {} {}",
         report
             .resolved
             .rust_version
@@ -83,6 +187,27 @@ fn print_detected_versions(report: &DetectionReport) {
             .as_deref()
             .unwrap_or(UNKNOWN_ANCHOR_VERSION)
     );
+    match (
+        report.resolved.platform_tools_version.as_deref(),
+        report.compatibility.sbf_toolchain,
+    ) {
+        (Some(version), Some(release)) => println!(
+            "SBF toolchain (cargo build-sbf): platform-tools {version} (rustc {})",
+            release.rustc
+        ),
+        (Some(version), None) => {
+            println!("SBF toolchain (cargo build-sbf): platform-tools {version}");
+        }
+        (None, _) => println!("SBF toolchain (cargo build-sbf): {UNKNOWN_VERSION}"),
+    }
+
+    for (path, program) in &report.program_binaries {
+        println!(
+            "Program {}: built with rustc {}",
+            path.display(),
+            program.rustc_version.as_deref().unwrap_or(UNKNOWN_VERSION)
+        );
+    }
 }
 fn print_current_environment(env: &anchor_version_detector::CurrentEnvironment) {
     println!("Current Environment:");
@@ -0,0 +1,486 @@
+use anyhow::{Result, anyhow};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::parse::requirement_lower_bound;
+use crate::types::{CompatibilityMatrix, CompatibilityRule, TestedRanges};
+
+const BUILT_IN_MATRIX: &str = include_str!("../data/compatibility.toml");
+const MAX_MATRIX_FILE_SIZE: usize = 1_000_000;
+
+static BUILT_IN: LazyLock<CompatibilityMatrix> = LazyLock::new(|| {
+    CompatibilityMatrix::from_toml_str(BUILT_IN_MATRIX)
+        .unwrap_or_else(|error| panic!("Built-in compatibility matrix is invalid: {error}"))
+});
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MatrixFile {
+    #[serde(default)]
+    rules: Vec<RuleEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    /// Each version is read as a raw value and checked by
+    /// [`VersionEntry::from_value`], so that errors name the rule and field.
+    #[serde(default)]
+    solana: Value,
+    #[serde(default)]
+    anchor: Value,
+    /// Defaults to the recommended `anchor` version, as the TypeScript client
+    /// is released in lockstep.
+    ts_client: Option<String>,
+    #[serde(default)]
+    rust: Value,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    source: String,
+}
+
+/// A rule version: either a single release, or a tested range with the
+/// release to recommend from it.
+#[derive(Debug)]
+enum VersionEntry {
+    Point(String),
+    Range(RangeEntry),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RangeEntry {
+    /// A Cargo-style requirement. Alternatively, give `min` and/or
+    /// `max_tested`, which form the range `>=min, <=max_tested`.
+    range: Option<String>,
+    min: Option<String>,
+    max_tested: Option<String>,
+    /// Defaults to the lower bound of the range.
+    recommended: Option<String>,
+}
+
+impl RangeEntry {
+    fn range(&self, field: &str) -> Result<String> {
+        if let Some(range) = &self.range {
+            if self.min.is_some() || self.max_tested.is_some() {
+                return Err(anyhow!(
+                    "{field} sets `range` together with `min` or `max_tested`"
+                ));
+            }
+            return Ok(range.clone());
+        }
+
+        let bounds: Vec<String> = [(">=", &self.min), ("<=", &self.max_tested)]
+            .into_iter()
+            .filter_map(|(op, version)| version.as_ref().map(|version| format!("{op}{version}")))
+            .collect();
+        if bounds.is_empty() {
+            return Err(anyhow!("{field} needs `range`, `min` or `max_tested`"));
+        }
+        Ok(bounds.join(", "))
+    }
+}
+
+impl VersionEntry {
+    fn from_value(value: Value, field: &str) -> Result<Self> {
+        match value {
+            Value::String(version) => Ok(Self::Point(version)),
+            Value::Object(_) => serde_json::from_value(value)
+                .map(Self::Range)
+                .map_err(|error| anyhow!("invalid {field} entry: {error}")),
+            Value::Null => Err(anyhow!("missing {field} version")),
+            other => Err(anyhow!(
+                "{field} must be a version string or a table with `range`, `min`, `max_tested` or `recommended`, found {other}"
+            )),
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            Self::Point(version) => version,
+            Self::Range(entry) => [
+                &entry.recommended,
+                &entry.min,
+                &entry.range,
+                &entry.max_tested,
+            ]
+            .into_iter()
+            .find_map(Option::as_deref)
+            .unwrap_or_default(),
+        }
+    }
+
+    /// The recommended version and tested range. A point version `X` is
+    /// tested as `=X`.
+    fn resolve(self, field: &str) -> Result<(String, VersionReq)> {
+        match self {
+            Self::Point(version) => {
+                let tested = VersionReq::parse(&format!("={version}"))
+                    .map_err(|error| anyhow!("invalid {field} version {version:?}: {error}"))?;
+                Ok((version, tested))
+            }
+            Self::Range(entry) => {
+                let range = entry.range(field)?;
+                let tested = VersionReq::parse(&range)
+                    .map_err(|error| anyhow!("invalid {field} range {range:?}: {error}"))?;
+                let recommended = match entry.recommended {
+                    Some(recommended) => recommended,
+                    None => requirement_lower_bound(&tested)
+                        .ok_or_else(|| {
+                            anyhow!("{field} range {range:?} has no lower bound; set `recommended`")
+                        })?
+                        .to_string(),
+                };
+                Ok((recommended, tested))
+            }
+        }
+    }
+}
+
+fn rules_from_entries(entries: Vec<RuleEntry>) -> Result<Vec<CompatibilityRule>> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let anchor_entry = VersionEntry::from_value(entry.anchor, "anchor")
+                .map_err(|error| anyhow!("rules[{index}]: {error}"))?;
+            let label = anchor_entry.label().to_string();
+            let context =
+                |error: anyhow::Error| anyhow!("rules[{index}] (anchor {label}): {error}");
+
+            let (anchor, anchor_range) = anchor_entry.resolve("anchor").map_err(context)?;
+            let (solana, solana_range) = VersionEntry::from_value(entry.solana, "solana")
+                .and_then(|solana| solana.resolve("solana"))
+                .map_err(context)?;
+            let (rust, rust_range) = VersionEntry::from_value(entry.rust, "rust")
+                .and_then(|rust| rust.resolve("rust"))
+                .map_err(context)?;
+            Ok(CompatibilityRule {
+                ts_client: entry.ts_client.unwrap_or_else(|| anchor.clone()),
+                solana,
+                anchor,
+                rust,
+                tested: TestedRanges {
+                    anchor: anchor_range,
+                    solana: solana_range,
+                    rust: rust_range,
+                },
+                notes: entry.notes,
+                source: entry.source,
+            })
+        })
+        .collect()
+}
+
+impl CompatibilityMatrix {
+    /// The matrix shipped with this crate, from `data/compatibility.toml`.
+    #[must_use]
+    pub fn built_in() -> &'static Self {
+        &BUILT_IN
+    }
+
+    /// Build a matrix from `rules`, ordered newest Anchor first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending rule when the rules fail
+    /// [`CompatibilityMatrix::validate`].
+    pub fn new(rules: Vec<CompatibilityRule>) -> Result<Self> {
+        let mut matrix = Self { rules };
+        matrix.validate()?;
+        matrix.sort();
+        Ok(matrix)
+    }
+
+    /// Parse a matrix from TOML with one `[[rules]]` table per rule.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the input is not valid TOML, has unknown keys, or
+    /// fails [`CompatibilityMatrix::validate`].
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let file: MatrixFile = toml::from_str(content)?;
+        Self::new(rules_from_entries(file.rules)?)
+    }
+
+    /// Parse a matrix from JSON of the form `{"rules": [...]}`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the input is not valid JSON, has unknown keys, or
+    /// fails [`CompatibilityMatrix::validate`].
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        let file: MatrixFile = serde_json::from_str(content)?;
+        Self::new(rules_from_entries(file.rules)?)
+    }
+
+    /// Load a matrix from a `.json` file, or from TOML for any other
+    /// extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file cannot be read, exceeds the size limit,
+    /// or does not hold a valid matrix.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|error| anyhow!("Failed to read {}: {}", path.display(), error))?;
+
+        if content.len() > MAX_MATRIX_FILE_SIZE {
+            return Err(anyhow!("File {} is too large (>1MB)", path.display()));
+        }
+
+        let matrix = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            Self::from_json_str(&content)
+        } else {
+            Self::from_toml_str(&content)
+        };
+        matrix
+            .map_err(|error| anyhow!("Invalid compatibility matrix {}: {}", path.display(), error))
+    }
+
+    /// Check that the matrix has rules, that every version is a full semver
+    /// version inside its tested range, and that no Anchor version appears
+    /// twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first offending rule by index and Anchor
+    /// version, e.g. `rules[3] (anchor 0.31.2): invalid solana version "2.x"`.
+    pub fn validate(&self) -> Result<()> {
+        if self.rules.is_empty() {
+            return Err(anyhow!("Compatibility matrix has no rules"));
+        }
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            for (field, value) in [
+                ("anchor", &rule.anchor),
+                ("solana", &rule.solana),
+                ("rust", &rule.rust),
+                ("ts_client", &rule.ts_client),
+            ] {
+                Version::parse(value).map_err(|error| {
+                    anyhow!(
+                        "rules[{index}] (anchor {}): invalid {field} version {value:?}: {error}",
+                        rule.anchor
+                    )
+                })?;
+            }
+
+            for (field, recommended, tested) in [
+                ("anchor", &rule.anchor, &rule.tested.anchor),
+                ("solana", &rule.solana, &rule.tested.solana),
+                ("rust", &rule.rust, &rule.tested.rust),
+            ] {
+                if Version::parse(recommended).is_ok_and(|version| !tested.matches(&version)) {
+                    return Err(anyhow!(
+                        "rules[{index}] (anchor {}): recommended {field} version {recommended} is outside its tested range {tested}",
+                        rule.anchor
+                    ));
+                }
+            }
+
+            if let Some(first) = self
+                .rules
+                .iter()
+                .position(|other| same_anchor(other, rule))
+                .filter(|first| *first != index)
+            {
+                return Err(anyhow!(
+                    "rules[{index}] (anchor {}): duplicates rules[{first}]",
+                    rule.anchor
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge `overrides` into this matrix. A rule for an Anchor version that is
+    /// already present replaces it, and other rules are added.
+    pub fn merge(&mut self, overrides: Self) {
+        for rule in overrides.rules {
+            if let Some(existing) = self
+                .rules
+                .iter_mut()
+                .find(|existing| same_anchor(existing, &rule))
+            {
+                *existing = rule;
+            } else {
+                self.rules.push(rule);
+            }
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.rules
+            .sort_by_key(|rule| Reverse(Version::parse(&rule.anchor).ok()));
+    }
+}
+
+fn same_anchor(left: &CompatibilityRule, right: &CompatibilityRule) -> bool {
+    match (Version::parse(&left.anchor), Version::parse(&right.anchor)) {
+        (Ok(left), Ok(right)) => left == right,
+        _ => left.anchor == right.anchor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MatchKind;
+
+    #[test]
+    fn test_built_in_matrix_is_valid_and_newest_first() {
+        let matrix = CompatibilityMatrix::built_in();
+        assert!(matrix.validate().is_ok());
+        assert_eq!(matrix.rules.len(), 17);
+        assert_eq!(matrix.rules[0].anchor, "1.1.2");
+        assert_eq!(matrix.rules[16].anchor, "0.26.0");
+    }
+
+    #[test]
+    fn test_project_file_overrides_and_extends_built_in_rules() {
+        let overrides = CompatibilityMatrix::from_toml_str(
+            r#"
+[[rules]]
+anchor = "1.2.0"
+solana = "3.1.10"
+rust = "1.90.0"
+
+[[rules]]
+anchor = "0.30.1"
+solana = "1.18.26"
+rust = "1.79.0"
+notes = "Tested internally."
+"#,
+        )
+        .unwrap();
+
+        let mut matrix = CompatibilityMatrix::built_in().clone();
+        matrix.merge(overrides);
+
+        assert_eq!(matrix.rules.len(), 18);
+        assert_eq!(matrix.rules[0].anchor, "1.2.0");
+        assert_eq!(matrix.rules[0].ts_client, "1.2.0");
+        let rule = matrix.find_rule_by_anchor("0.30.1").unwrap();
+        assert_eq!(rule.solana, "1.18.26");
+        assert_eq!(rule.notes, "Tested internally.");
+    }
+
+    #[test]
+    fn test_validation_errors_name_the_offending_rule() {
+        let error = CompatibilityMatrix::from_json_str(
+            r#"{"rules": [
+                {"anchor": "0.31.0", "solana": "2.1.0", "rust": "1.84.1"},
+                {"anchor": "0.31.2", "solana": "2.x", "rust": "1.84.1"}
+            ]}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[1] (anchor 0.31.2): invalid solana version \"2.x\": unexpected character 'x' while parsing minor version number"
+        );
+
+        let error = CompatibilityMatrix::from_toml_str(
+            "[[rules]]\nanchor = \"0.31.0\"\nsolana = \"2.1.0\"\nrust = \"1.84.1\"\n\n[[rules]]\nanchor = \"0.31.0\"\nsolana = \"2.1.1\"\nrust = \"1.84.1\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[1] (anchor 0.31.0): duplicates rules[0]"
+        );
+
+        let error = CompatibilityMatrix::from_toml_str(
+            "[[rules]]\nanchor = \"0.31.0\"\nsolana = 2.1\nrust = \"1.84.1\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[0] (anchor 0.31.0): solana must be a version string or a table with `range`, `min`, `max_tested` or `recommended`, found 2.1"
+        );
+        let error = CompatibilityMatrix::from_toml_str(
+            "[[rules]]\nanchor = \"0.31.0\"\nsolana = \"2.1.0\"\nrust = { maximum = \"1.84.1\" }\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[0] (anchor 0.31.0): invalid rust entry: unknown field `maximum`, expected one of `range`, `min`, `max_tested`, `recommended`"
+        );
+        let error =
+            CompatibilityMatrix::from_toml_str("[[rules]]\nanchor = \"0.31.0\"\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[0] (anchor 0.31.0): missing solana version"
+        );
+        assert!(CompatibilityMatrix::from_toml_str("").is_err());
+    }
+
+    #[test]
+    fn test_range_rules_return_recommended_versions_and_tested_bounds() {
+        let matrix = CompatibilityMatrix::from_toml_str(
+            r#"
+[[rules]]
+anchor = { range = ">=0.31, <0.32", recommended = "0.31.1" }
+solana = { range = ">=2.1, <2.2", recommended = "2.1.0" }
+rust = { range = ">=1.84.1" }
+
+[[rules]]
+anchor = "0.30.1"
+solana = "1.18.17"
+rust = "1.75.0"
+"#,
+        )
+        .unwrap();
+
+        let matched = matrix.match_rule_by_anchor("0.31.0").unwrap();
+        assert_eq!(matched.kind, MatchKind::InRange);
+        assert_eq!(
+            (
+                matched.rule.anchor.as_str(),
+                matched.rule.solana.as_str(),
+                matched.rule.rust.as_str()
+            ),
+            ("0.31.1", "2.1.0", "1.84.1")
+        );
+        assert_eq!(matched.rule.ts_client, "0.31.1");
+        assert_eq!(matched.rule.tested.solana.to_string(), ">=2.1, <2.2");
+
+        let matched = matrix.match_rule_by_solana("2.1.21").unwrap();
+        assert_eq!(matched.kind, MatchKind::InRange);
+        let requirement = VersionReq::parse("^2.1.14").unwrap();
+        let matched = matrix
+            .match_rule_by_solana_requirement(&requirement)
+            .unwrap();
+        assert_eq!(matched.rule.anchor, "0.31.1");
+
+        let point = matrix.find_rule_by_anchor("0.30.1").unwrap();
+        assert_eq!(point.tested.anchor.to_string(), "=0.30.1");
+
+        let error = CompatibilityMatrix::from_toml_str(
+            "[[rules]]\nanchor = \"0.31.0\"\nsolana = { range = \">=2.1, <2.2\", recommended = \"2.2.0\" }\nrust = \"1.84.1\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[0] (anchor 0.31.0): recommended solana version 2.2.0 is outside its tested range >=2.1, <2.2"
+        );
+        let error = CompatibilityMatrix::from_toml_str(
+            "[[rules]]\nanchor = \"0.31.0\"\nsolana = { range = \"<2.2\" }\nrust = \"1.84.1\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rules[0] (anchor 0.31.0): solana range \"<2.2\" has no lower bound; set `recommended`"
+        );
+    }
+}
@@ -1,27 +1,77 @@
 use anyhow::{Result, anyhow};
+use semver::{Op, Version, VersionReq};
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
-use crate::types::ProjectVersions;
+use crate::crates::{known_repositories, recognized_crates};
+use crate::types::{
+    AnchorConfig, AnchorFeatures, AnchorGenesisProgram, AnchorProgram, AnchorProvider,
+    AnchorTestConfig, AnchorToolchain, AnchorValidatorAccount, AnchorValidatorConfig,
+    AnchorWorkspace, CargoManifest, DependencyOrigin, DependencySignal, DependencyTable, GitSource,
+    IdlFormat, ManifestDependency, ProjectVersions, RecognizedCrate, RustToolchainSpec,
+    ToolchainChannel, VersionField, VersionRequirements,
+};
 
 #[derive(Deserialize)]
-struct RustToolchain {
-    toolchain: RustToolchainSpec,
+struct RustToolchainFile {
+    toolchain: RustToolchainTable,
 }
 
 #[derive(Deserialize)]
-struct RustToolchainSpec {
-    channel: String,
+struct RustToolchainTable {
+    channel: Option<String>,
+    components: Option<Vec<String>>,
+    targets: Option<Vec<String>>,
+    profile: Option<String>,
+    path: Option<PathBuf>,
 }
 
 #[derive(Debug, Deserialize)]
 struct CargoToml {
-    dependencies: Option<Dependencies>,
+    #[serde(flatten)]
+    tables: DependencyTables,
+    target: Option<BTreeMap<String, DependencyTables>>,
     workspace: Option<Workspace>,
+    package: Option<Package>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    #[serde(rename = "rust-version")]
+    rust_version: Option<InheritableString>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum InheritableString {
+    Value(String),
+    Inherited { workspace: bool },
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspacePackage {
+    #[serde(rename = "rust-version")]
+    rust_version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DependencyTables {
+    dependencies: Option<Dependencies>,
+    #[serde(rename = "dev-dependencies")]
+    dev_dependencies: Option<Dependencies>,
+    #[serde(rename = "build-dependencies")]
+    build_dependencies: Option<Dependencies>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Workspace {
     dependencies: Option<Dependencies>,
+    package: Option<WorkspacePackage>,
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,86 +84,727 @@ enum DependencySpec {
 #[derive(Debug, Deserialize)]
 struct DetailedDependency {
     version: Option<String>,
+    #[serde(default)]
+    workspace: bool,
+    package: Option<String>,
+    git: Option<String>,
+    tag: Option<String>,
+    rev: Option<String>,
+    branch: Option<String>,
     #[serde(flatten)]
+    #[allow(clippy::used_underscore_binding)]
     _other: std::collections::HashMap<String, toml::Value>,
 }
 
+type Dependencies = BTreeMap<String, DependencySpec>;
+
 #[derive(Debug, Deserialize)]
-struct Dependencies {
-    #[serde(rename = "solana-program")]
-    solana_program: Option<DependencySpec>,
-    #[serde(rename = "anchor-lang")]
-    anchor_lang: Option<DependencySpec>,
-    #[serde(rename = "anchor-spl")]
-    anchor_spl: Option<DependencySpec>,
+struct CargoLock {
+    #[serde(default, rename = "package")]
+    packages: Vec<LockedPackage>,
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct MiseToml {
+    tools: BTreeMap<String, MiseToolSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MiseToolSpec {
+    Version(String),
+    Versions(Vec<String>),
+    Detailed { version: String },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
 struct AnchorToml {
-    toolchain: Option<ToolchainConfig>,
+    toolchain: AnchorToolchainTable,
+    features: AnchorFeaturesTable,
+    workspace: AnchorWorkspaceTable,
+    programs: BTreeMap<String, BTreeMap<String, AnchorProgramSpec>>,
+    provider: AnchorProviderTable,
+    scripts: BTreeMap<String, String>,
+    test: Option<AnchorTestTable>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnchorToolchainTable {
+    anchor_version: Option<String>,
+    solana_version: Option<String>,
+    package_manager: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnchorFeaturesTable {
+    resolution: Option<bool>,
+    #[serde(rename = "skip-lint")]
+    skip_lint: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnchorWorkspaceTable {
+    members: Vec<String>,
+    exclude: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ToolchainConfig {
-    #[serde(rename = "anchor_version")]
-    anchor: Option<String>,
-    #[serde(rename = "solana_version")]
-    solana: Option<String>,
+#[serde(untagged)]
+enum AnchorProgramSpec {
+    Address(String),
+    Detailed {
+        address: String,
+        idl: Option<String>,
+    },
 }
 
-/// Parse a `rust-toolchain` file in TOML or plain-text form.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnchorProviderTable {
+    cluster: Option<String>,
+    wallet: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnchorTestTable {
+    startup_wait: Option<u64>,
+    shutdown_wait: Option<u64>,
+    upgradeable: Option<bool>,
+    genesis: Vec<AnchorGenesisEntry>,
+    validator: Option<AnchorValidatorTable>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnchorGenesisEntry {
+    address: String,
+    program: String,
+    upgradeable: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnchorValidatorTable {
+    url: Option<String>,
+    bind_address: Option<String>,
+    ledger: Option<String>,
+    rpc_port: Option<u16>,
+    clone: Vec<AnchorCloneEntry>,
+    account: Vec<AnchorAccountEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnchorCloneEntry {
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnchorAccountEntry {
+    address: String,
+    filename: String,
+}
+
+/// Parse a `rust-toolchain` file in TOML or plain-text form and return its
+/// channel name without any host triple.
 ///
 /// # Errors
 ///
 /// Returns an error when the input is neither a valid `rust-toolchain.toml`
-/// payload nor a plain-text version string.
+/// payload nor a plain-text toolchain name, or when it only names a `path`.
 pub fn parse_rust_toolchain(content: &str) -> Result<String> {
-    if let Ok(toolchain) = toml::from_str::<RustToolchain>(content) {
-        return Ok(toolchain.toolchain.channel);
+    parse_rust_toolchain_spec(content)?
+        .channel
+        .map(|channel| channel.name())
+        .ok_or_else(|| anyhow!("rust-toolchain does not name a channel"))
+}
+
+/// Parse a `rust-toolchain` file in TOML or plain-text form.
+///
+/// # Errors
+///
+/// Returns an error when the input is neither a valid `rust-toolchain.toml`
+/// payload nor a plain-text toolchain name.
+pub fn parse_rust_toolchain_spec(content: &str) -> Result<RustToolchainSpec> {
+    if let Ok(file) = toml::from_str::<RustToolchainFile>(content) {
+        let table = file.toolchain;
+        if table.channel.is_none() && table.path.is_none() {
+            return Err(anyhow!("rust-toolchain names neither a channel nor a path"));
+        }
+
+        let (channel, host) = table
+            .channel
+            .as_deref()
+            .map_or((None, None), parse_toolchain_name);
+        return Ok(RustToolchainSpec {
+            channel,
+            host,
+            components: table.components.unwrap_or_default(),
+            targets: table.targets.unwrap_or_default(),
+            profile: table.profile,
+            path: table.path,
+        });
     }
 
-    let version = content.trim();
-    if version.chars().any(char::is_numeric) {
-        Ok(version.to_string())
+    let name = content.trim();
+    let (channel, host) = parse_toolchain_name(name);
+    let is_known = !matches!(channel, Some(ToolchainChannel::Custom(_)));
+    if is_known || (!name.contains(char::is_whitespace) && name.chars().any(char::is_numeric)) {
+        Ok(RustToolchainSpec {
+            channel,
+            host,
+            ..RustToolchainSpec::default()
+        })
     } else {
         Err(anyhow!("Invalid rust-toolchain format"))
     }
 }
 
+/// Split a toolchain name such as `nightly-2023-10-29-x86_64-unknown-linux-gnu`
+/// into its channel and host triple.
+fn parse_toolchain_name(name: &str) -> (Option<ToolchainChannel>, Option<String>) {
+    let (head, rest) = match name.split_once('-') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (name, None),
+    };
+
+    let dated = matches!(head, "beta" | "nightly");
+    let (date, host) = match rest {
+        Some(rest) if dated && rest.get(..10).is_some_and(is_toolchain_date) => {
+            let host = rest.get(11..).filter(|host| !host.is_empty());
+            (rest.get(..10), host)
+        }
+        _ => (None, rest),
+    };
+    let date = date.map(str::to_string);
+
+    let channel = match head {
+        "stable" => ToolchainChannel::Stable,
+        "beta" => ToolchainChannel::Beta { date },
+        "nightly" => ToolchainChannel::Nightly { date },
+        _ if is_release_version(head) => ToolchainChannel::Version(head.to_string()),
+        _ if name.is_empty() => return (None, None),
+        _ => return (Some(ToolchainChannel::Custom(name.to_string())), None),
+    };
+    (Some(channel), host.map(str::to_string))
+}
+
+fn is_toolchain_date(value: &str) -> bool {
+    value.len() == 10
+        && value.char_indices().all(|(index, character)| match index {
+            4 | 7 => character == '-',
+            _ => character.is_ascii_digit(),
+        })
+}
+
+fn is_release_version(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('.').collect();
+    matches!(parts.len(), 2 | 3)
+        && parts.iter().all(|part| {
+            !part.is_empty() && part.chars().all(|character| character.is_ascii_digit())
+        })
+}
+
 #[must_use]
 pub fn parse_anchor_toml(content: &str) -> ProjectVersions {
-    match toml::from_str::<AnchorToml>(content) {
-        Ok(config) => {
-            let mut versions = ProjectVersions::default();
-            if let Some(toolchain) = config.toolchain {
-                versions.solana_version = toolchain.solana;
-                versions.anchor_version = toolchain.anchor;
+    match parse_anchor_config(content) {
+        Ok(config) => with_exact_requirements(ProjectVersions {
+            solana_version: config.toolchain.solana_version,
+            anchor_version: config.toolchain.anchor_version,
+            ..ProjectVersions::default()
+        }),
+        Err(_) => parse_anchor_toml_fallback(content),
+    }
+}
+
+/// Parse every section of an `Anchor.toml` that carries project settings.
+///
+/// # Errors
+///
+/// Returns an error when the input is not valid TOML or a known section has
+/// an unexpected shape.
+pub fn parse_anchor_config(content: &str) -> Result<AnchorConfig> {
+    let parsed = toml::from_str::<AnchorToml>(content)
+        .map_err(|error| anyhow!("Invalid Anchor.toml: {error}"))?;
+
+    Ok(AnchorConfig {
+        toolchain: AnchorToolchain {
+            anchor_version: parsed.toolchain.anchor_version,
+            solana_version: parsed.toolchain.solana_version,
+            package_manager: parsed.toolchain.package_manager,
+        },
+        features: AnchorFeatures {
+            resolution: parsed.features.resolution,
+            skip_lint: parsed.features.skip_lint,
+        },
+        workspace: AnchorWorkspace {
+            members: parsed.workspace.members,
+            exclude: parsed.workspace.exclude,
+        },
+        programs: parsed
+            .programs
+            .into_iter()
+            .map(|(cluster, programs)| {
+                let programs = programs
+                    .into_iter()
+                    .map(|(name, spec)| {
+                        let program = match spec {
+                            AnchorProgramSpec::Address(address) => {
+                                AnchorProgram { address, idl: None }
+                            }
+                            AnchorProgramSpec::Detailed { address, idl } => {
+                                AnchorProgram { address, idl }
+                            }
+                        };
+                        (name, program)
+                    })
+                    .collect();
+                (cluster, programs)
+            })
+            .collect(),
+        provider: AnchorProvider {
+            cluster: parsed.provider.cluster,
+            wallet: parsed.provider.wallet,
+        },
+        scripts: parsed.scripts,
+        test: parsed.test.map(|test| AnchorTestConfig {
+            startup_wait: test.startup_wait,
+            shutdown_wait: test.shutdown_wait,
+            upgradeable: test.upgradeable,
+            genesis: test
+                .genesis
+                .into_iter()
+                .map(|entry| AnchorGenesisProgram {
+                    address: entry.address,
+                    program: entry.program,
+                    upgradeable: entry.upgradeable,
+                })
+                .collect(),
+            validator: test.validator.map(|validator| AnchorValidatorConfig {
+                url: validator.url,
+                bind_address: validator.bind_address,
+                ledger: validator.ledger,
+                rpc_port: validator.rpc_port,
+                clone: validator
+                    .clone
+                    .into_iter()
+                    .map(|entry| entry.address)
+                    .collect(),
+                account: validator
+                    .account
+                    .into_iter()
+                    .map(|entry| AnchorValidatorAccount {
+                        address: entry.address,
+                        filename: entry.filename,
+                    })
+                    .collect(),
+            }),
+        }),
+    })
+}
+
+/// Parse an asdf or mise `.tool-versions` file. The first version listed for
+/// `rust`, `solana` (or `agave`) and `anchor` is used.
+#[must_use]
+pub fn parse_tool_versions(content: &str) -> ProjectVersions {
+    let mut versions = ProjectVersions::default();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        if let (Some(tool), Some(version)) = (fields.next(), fields.next()) {
+            assign_tool_version(&mut versions, tool, version);
+        }
+    }
+    with_exact_requirements(versions)
+}
+
+/// Parse the `[tools]` table of a `mise.toml` or `.mise.toml` file. Backend
+/// prefixes such as `cargo:anchor-cli` or `ubi:solana-labs/solana` are
+/// accepted.
+#[must_use]
+pub fn parse_mise_toml(content: &str) -> ProjectVersions {
+    let mut versions = ProjectVersions::default();
+    if let Ok(config) = toml::from_str::<MiseToml>(content) {
+        for (tool, spec) in &config.tools {
+            let version = match spec {
+                MiseToolSpec::Version(version) | MiseToolSpec::Detailed { version } => {
+                    Some(version)
+                }
+                MiseToolSpec::Versions(versions) => versions.first(),
+            };
+            if let Some(version) = version {
+                assign_tool_version(&mut versions, tool, version);
             }
-            versions
         }
-        Err(_) => parse_anchor_toml_fallback(content),
     }
+    with_exact_requirements(versions)
+}
+
+fn assign_tool_version(versions: &mut ProjectVersions, tool: &str, version: &str) {
+    let name = tool.rsplit([':', '/']).next().unwrap_or(tool);
+    let (slot, version) = match name {
+        "rust" => (
+            &mut versions.rust_version,
+            parse_rust_toolchain_spec(version)
+                .ok()
+                .and_then(|spec| spec.channel)
+                .filter(|channel| !matches!(channel, ToolchainChannel::Custom(_)))
+                .map(|channel| channel.name()),
+        ),
+        "solana" | "agave" => (&mut versions.solana_version, pinned_release(version)),
+        "anchor" | "anchor-cli" => (&mut versions.anchor_version, pinned_release(version)),
+        _ => return,
+    };
+
+    if slot.is_none() {
+        *slot = version;
+    }
+}
+
+/// A release number from a version manager, skipping aliases such as
+/// `latest` or `system`.
+fn pinned_release(version: &str) -> Option<String> {
+    let version = clean_version(version.trim());
+    version
+        .starts_with(|character: char| character.is_ascii_digit())
+        .then_some(version)
+}
+
+/// Parse a `.anchorversion` file, which holds a single Anchor version such as
+/// `0.30.1` or `v0.30.1`.
+///
+/// # Errors
+///
+/// Returns an error when the file does not contain a valid version.
+pub fn parse_anchor_version_file(content: &str) -> Result<String> {
+    let version = clean_version(content.trim());
+    semver::Version::parse(&version)
+        .map(|_| version)
+        .map_err(|error| anyhow!("Invalid .anchorversion: {error}"))
 }
 
 #[must_use]
 pub fn parse_cargo_toml(content: &str) -> ProjectVersions {
+    versions_from_signals(&parse_cargo_dependencies(content, recognized_crates()))
+}
+
+/// Collect a version signal for every recognised crate in a `Cargo.toml`.
+///
+/// Signals are listed in manifest order: `[dependencies]`,
+/// `[dev-dependencies]` and `[build-dependencies]`, then the
+/// `[target.'cfg(..)'.*]` tables, then `[workspace.dependencies]`. Entries
+/// inherited with `workspace = true` carry no version of their own and are
+/// skipped.
+#[must_use]
+pub fn parse_cargo_dependencies(
+    content: &str,
+    crates: &[RecognizedCrate],
+) -> Vec<DependencySignal> {
+    dependency_signals(&parse_cargo_manifest(content).dependencies, crates)
+}
+
+/// Parse the dependency tables of a `Cargo.toml`, including dev, build and
+/// target-specific tables.
+#[must_use]
+pub fn parse_cargo_manifest(content: &str) -> CargoManifest {
     match toml::from_str::<CargoToml>(content) {
         Ok(config) => {
-            let mut versions = ProjectVersions::default();
-            if let Some(deps) = &config.dependencies {
-                update_versions_from_dependencies(&mut versions, deps);
+            let package_rust_version = config
+                .package
+                .as_ref()
+                .and_then(|package| package.rust_version.as_ref());
+            let mut manifest = CargoManifest {
+                is_workspace_root: config.workspace.is_some(),
+                rust_version: match package_rust_version {
+                    Some(InheritableString::Value(version)) => Some(version.clone()),
+                    _ => None,
+                },
+                inherits_rust_version: matches!(
+                    package_rust_version,
+                    Some(InheritableString::Inherited { workspace: true })
+                ),
+                workspace_rust_version: config
+                    .workspace
+                    .as_ref()
+                    .and_then(|workspace| workspace.package.as_ref())
+                    .and_then(|package| package.rust_version.clone()),
+                workspace_members: config
+                    .workspace
+                    .as_ref()
+                    .map(|workspace| workspace.members.clone())
+                    .unwrap_or_default(),
+                workspace_exclude: config
+                    .workspace
+                    .as_ref()
+                    .map(|workspace| workspace.exclude.clone())
+                    .unwrap_or_default(),
+                ..CargoManifest::default()
+            };
+            collect_dependency_tables(&mut manifest.dependencies, &config.tables, None);
+            for (target, tables) in config.target.iter().flatten() {
+                collect_dependency_tables(&mut manifest.dependencies, tables, Some(target));
             }
             if let Some(workspace) = &config.workspace
                 && let Some(workspace_deps) = &workspace.dependencies
             {
-                update_versions_from_dependencies(&mut versions, workspace_deps);
+                collect_manifest_dependencies(
+                    &mut manifest.dependencies,
+                    workspace_deps,
+                    DependencyTable::Workspace,
+                    None,
+                );
             }
-            versions
+            manifest
+        }
+        Err(_) => parse_cargo_manifest_fallback(content),
+    }
+}
+
+/// Turn the recognised, versioned entries of `dependencies` into signals.
+///
+/// Git dependencies without a `version` use the release named by their tag.
+#[must_use]
+pub fn dependency_signals<'a>(
+    dependencies: impl IntoIterator<Item = &'a ManifestDependency>,
+    crates: &[RecognizedCrate],
+) -> Vec<DependencySignal> {
+    dependencies
+        .into_iter()
+        .filter_map(|dependency| {
+            let recognized = recognize(crates, &dependency.name)?;
+            let (version, requirement) = if let Some(requirement) = &dependency.requirement {
+                (
+                    parse_semver_range(requirement),
+                    parse_version_requirement(requirement),
+                )
+            } else {
+                // A rev or branch cannot be mapped to a release, but it still
+                // shows the project depends on the crate.
+                dependency.git.as_ref()?.tag_version().map_or_else(
+                    || ("*".to_string(), None),
+                    |version| {
+                        let requirement = exact_version_requirement(&version);
+                        (version, requirement)
+                    },
+                )
+            };
+            Some(DependencySignal {
+                field: recognized.field,
+                weight: recognized.weight,
+                version,
+                requirement,
+                origin: DependencyOrigin {
+                    crate_name: dependency.name.clone(),
+                    alias: dependency.alias.clone(),
+                    table: Some(dependency.table),
+                    target: dependency.target.clone(),
+                    inherited_from: None,
+                    git: dependency.git.clone(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Extract the resolved Solana and Anchor versions from a `Cargo.lock` file.
+///
+/// When a package is locked at several versions the highest one is reported.
+/// Malformed lockfiles yield no versions.
+#[must_use]
+pub fn parse_cargo_lock(content: &str) -> ProjectVersions {
+    versions_from_signals(&parse_cargo_lock_dependencies(content, recognized_crates()))
+}
+
+/// Collect a version signal for every recognised crate in a `Cargo.lock`,
+/// keeping the highest locked version of each package.
+#[must_use]
+pub fn parse_cargo_lock_dependencies(
+    content: &str,
+    crates: &[RecognizedCrate],
+) -> Vec<DependencySignal> {
+    let Ok(lock) = toml::from_str::<CargoLock>(content) else {
+        return Vec::new();
+    };
+
+    let mut highest: BTreeMap<&str, (semver::Version, &RecognizedCrate)> = BTreeMap::new();
+    for package in &lock.packages {
+        let Some(recognized) = recognize(crates, &package.name) else {
+            continue;
+        };
+        let Ok(version) = semver::Version::parse(&package.version) else {
+            continue;
+        };
+        if highest
+            .get(package.name.as_str())
+            .is_none_or(|(current, _)| version > *current)
+        {
+            highest.insert(&package.name, (version, recognized));
         }
-        Err(_) => parse_cargo_toml_fallback(content),
+    }
+
+    highest
+        .into_iter()
+        .map(|(name, (version, recognized))| DependencySignal {
+            field: recognized.field,
+            weight: recognized.weight,
+            version: version.to_string(),
+            requirement: exact_version_requirement(&version.to_string()),
+            origin: DependencyOrigin {
+                crate_name: name.to_string(),
+                alias: None,
+                table: None,
+                target: None,
+                inherited_from: None,
+                git: None,
+            },
+        })
+        .collect()
+}
+
+/// Pick the strongest signal for `field`.
+///
+/// Signals from normal dependency tables beat dev and build dependencies;
+/// within the same table rank the highest crate weight wins, and the earliest
+/// signal wins ties.
+#[must_use]
+pub fn strongest_signal(
+    signals: &[DependencySignal],
+    field: VersionField,
+) -> Option<&DependencySignal> {
+    let rank = |signal: &DependencySignal| {
+        (
+            signal.version != "*",
+            signal.origin.table.map_or(
+                DependencyTable::Normal.precedence(),
+                DependencyTable::precedence,
+            ),
+            signal.weight,
+        )
+    };
+
+    signals.iter().filter(|signal| signal.field == field).fold(
+        None,
+        |best: Option<&DependencySignal>, signal| match best {
+            Some(current) if rank(current) >= rank(signal) => Some(current),
+            _ => Some(signal),
+        },
+    )
+}
+
+/// Describe a git dependency source, recognising the repository from
+/// [`known_repositories`].
+#[must_use]
+pub fn parse_git_source(
+    url: &str,
+    tag: Option<&str>,
+    rev: Option<&str>,
+    branch: Option<&str>,
+) -> GitSource {
+    let repository = repository_slug(url);
+    let repository_kind = repository.as_deref().and_then(|slug| {
+        known_repositories()
+            .iter()
+            .find(|known| known.slug.eq_ignore_ascii_case(slug))
+            .map(|known| known.kind)
+    });
+
+    GitSource {
+        url: url.to_string(),
+        repository,
+        repository_kind,
+        tag: tag.map(std::string::ToString::to_string),
+        rev: rev.map(std::string::ToString::to_string),
+        branch: branch.map(std::string::ToString::to_string),
     }
 }
 
+/// Parse a Cargo-style version requirement such as `>=1.18,<2` or `0.30.1`.
+///
+/// Bare versions use Cargo's caret semantics, so `0.30.1` means
+/// `>=0.30.1, <0.31.0`.
+#[must_use]
+pub fn parse_version_requirement(requirement: &str) -> Option<VersionReq> {
+    VersionReq::parse(requirement.trim().trim_start_matches('v')).ok()
+}
+
+/// Build a requirement pinning exactly `version`, as used for lockfile
+/// entries and toolchain pins. Partial versions such as `1.18` pin the
+/// whole minor release.
+#[must_use]
+pub fn exact_version_requirement(version: &str) -> Option<VersionReq> {
+    VersionReq::parse(&format!("={}", clean_version(version.trim()))).ok()
+}
+
+/// The lowest version `requirement` admits, or `None` when it has no lower
+/// bound, as in `<0.32` or `*`.
+#[must_use]
+pub fn requirement_lower_bound(requirement: &VersionReq) -> Option<Version> {
+    requirement
+        .comparators
+        .iter()
+        .filter_map(|comparator| {
+            let minor = comparator.minor.unwrap_or(0);
+            let patch = comparator.patch.unwrap_or(0);
+            let mut bound = match comparator.op {
+                Op::Less | Op::LessEq => return None,
+                Op::Greater => match (comparator.minor, comparator.patch) {
+                    (Some(minor), Some(patch)) => {
+                        Version::new(comparator.major, minor, patch.saturating_add(1))
+                    }
+                    (Some(minor), None) => {
+                        Version::new(comparator.major, minor.saturating_add(1), 0)
+                    }
+                    _ => Version::new(comparator.major.saturating_add(1), 0, 0),
+                },
+                _ => Version::new(comparator.major, minor, patch),
+            };
+            if comparator.op != Op::Greater {
+                bound.pre = comparator.pre.clone();
+            }
+            Some(bound)
+        })
+        .max()
+}
+
+/// The highest version `requirement` admits, when a `<=` or `=` comparator
+/// names a full version. Exclusive bounds such as `<2.2` give `None`, as they
+/// do not name a version that was actually tested.
+#[must_use]
+pub fn requirement_upper_bound(requirement: &VersionReq) -> Option<Version> {
+    requirement
+        .comparators
+        .iter()
+        .filter(|comparator| matches!(comparator.op, Op::Exact | Op::LessEq))
+        .filter_map(|comparator| {
+            let mut bound = Version::new(comparator.major, comparator.minor?, comparator.patch?);
+            bound.pre = comparator.pre.clone();
+            Some(bound)
+        })
+        .min()
+}
+
+/// Whether some version satisfies both requirements.
+///
+/// Cargo requirements describe a single interval, so two of them overlap
+/// exactly when the higher of their lower bounds satisfies both.
+#[must_use]
+pub fn requirements_overlap(left: &VersionReq, right: &VersionReq) -> bool {
+    [left, right].into_iter().any(|requirement| {
+        let bound = requirement_lower_bound(requirement).unwrap_or_else(|| Version::new(0, 0, 0));
+        left.matches(&bound) && right.matches(&bound)
+    })
+}
+
 #[must_use]
 pub fn parse_semver_range(version_str: &str) -> String {
     let version_str = version_str.trim();
@@ -150,6 +841,222 @@ pub fn clean_version(version: &str) -> String {
         .to_string()
 }
 
+/// Tell the legacy and 0.30+ Anchor IDL layouts apart.
+///
+/// # Errors
+///
+/// Returns an error when the input is not JSON or does not look like an
+/// Anchor IDL.
+pub fn parse_idl(content: &str) -> Result<IdlFormat> {
+    let value = serde_json::from_str::<serde_json::Value>(content)
+        .map_err(|error| anyhow!("Invalid IDL: {error}"))?;
+
+    if let Some(spec) = value
+        .get("metadata")
+        .and_then(|metadata| metadata.get("spec"))
+        .and_then(serde_json::Value::as_str)
+    {
+        return Ok(IdlFormat::Spec(spec.to_string()));
+    }
+
+    if value
+        .get("version")
+        .is_some_and(serde_json::Value::is_string)
+        && value.get("instructions").is_some()
+    {
+        return Ok(IdlFormat::Legacy);
+    }
+
+    Err(anyhow!("Not an Anchor IDL"))
+}
+
+/// Read `dependencies` and `devDependencies` from a `package.json`, keyed by
+/// package name. `dependencies` wins when a package is listed in both.
+///
+/// # Errors
+///
+/// Returns an error when the input is not valid JSON.
+pub fn parse_package_json(content: &str) -> Result<BTreeMap<String, String>> {
+    let value = serde_json::from_str::<serde_json::Value>(content)
+        .map_err(|error| anyhow!("Invalid package.json: {error}"))?;
+
+    let mut packages = BTreeMap::new();
+    for table in ["devDependencies", "dependencies"] {
+        for (name, requirement) in value
+            .get(table)
+            .and_then(serde_json::Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(requirement) = requirement.as_str() {
+                packages.insert(name.clone(), requirement.to_string());
+            }
+        }
+    }
+    Ok(packages)
+}
+
+/// Read the resolved versions of the direct dependencies in `dependencies`,
+/// keyed by package name with their `package.json` range, from a classic or
+/// Berry `yarn.lock`.
+///
+/// Only the entry resolving the range declared in `package.json` is used, so
+/// copies pulled in at other ranges by transitive dependencies are ignored.
+#[must_use]
+pub fn parse_yarn_lock(
+    content: &str,
+    dependencies: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut packages = BTreeMap::new();
+    let mut names: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            names = line
+                .trim_end()
+                .trim_end_matches(':')
+                .split(", ")
+                .filter_map(|entry| {
+                    let specifier = entry.trim().trim_matches('"');
+                    let name = js_package_name(specifier)?;
+                    let range = specifier.get(name.len()..)?.trim_start_matches('@');
+                    let declared = dependencies.get(name)?;
+                    (range == declared || range.strip_prefix("npm:") == Some(declared))
+                        .then_some(name)
+                })
+                .collect();
+            continue;
+        }
+
+        if line.starts_with("   ") {
+            continue;
+        }
+        if let Some(version) = line.trim().strip_prefix("version") {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            for name in &names {
+                packages.insert((*name).to_string(), version.to_string());
+            }
+        }
+    }
+    packages
+}
+
+/// Read resolved package versions from an npm `package-lock.json` (lockfile
+/// versions 1 to 3).
+///
+/// Only top-level `node_modules/<name>` entries are used. Those are what the
+/// project's own dependencies resolve to, whereas nested copies belong to
+/// transitive dependencies.
+#[must_use]
+pub fn parse_package_lock(content: &str) -> BTreeMap<String, String> {
+    let mut packages = BTreeMap::new();
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return packages;
+    };
+
+    for (path, entry) in value
+        .get("packages")
+        .and_then(serde_json::Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(name) = path.strip_prefix("node_modules/")
+            && !name.contains("/node_modules/")
+            && let Some(version) = entry.get("version").and_then(serde_json::Value::as_str)
+        {
+            packages.insert(name.to_string(), version.to_string());
+        }
+    }
+
+    for (name, entry) in value
+        .get("dependencies")
+        .and_then(serde_json::Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(version) = entry.get("version").and_then(serde_json::Value::as_str) {
+            packages
+                .entry(name.clone())
+                .or_insert_with(|| version.to_string());
+        }
+    }
+    packages
+}
+
+/// Read resolved package versions from the `packages` section of a
+/// `pnpm-lock.yaml`, keeping the highest version of each package.
+///
+/// Handles both the `/name/1.0.0` keys of lockfile v5 and the `name@1.0.0`
+/// keys of later versions, ignoring any peer-dependency suffix.
+#[must_use]
+pub fn parse_pnpm_lock(content: &str) -> BTreeMap<String, String> {
+    let mut packages = BTreeMap::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        if !in_packages || line.starts_with("   ") {
+            continue;
+        }
+
+        let key = line
+            .trim()
+            .trim_end_matches(':')
+            .trim_matches(['\'', '"'])
+            .trim_start_matches('/');
+        let (name, version) = match js_package_name(key) {
+            Some(name) => (name, key.get(name.len()..).unwrap_or_default()),
+            None => match key.rsplit_once('/') {
+                Some((name, version)) => (name, version),
+                None => continue,
+            },
+        };
+        let version = version
+            .trim_start_matches(['@', '/'])
+            .split(['(', '_'])
+            .next()
+            .unwrap_or_default();
+        if version.starts_with(|character: char| character.is_ascii_digit()) {
+            insert_highest(&mut packages, name, version);
+        }
+    }
+    packages
+}
+
+/// The package name in a `name@range` specifier, allowing for scoped
+/// packages such as `@coral-xyz/anchor@^0.30.1`.
+fn js_package_name(specifier: &str) -> Option<&str> {
+    specifier
+        .char_indices()
+        .skip(1)
+        .find(|(_, character)| *character == '@')
+        .and_then(|(index, _)| specifier.get(..index))
+}
+
+fn insert_highest(packages: &mut BTreeMap<String, String>, name: &str, version: &str) {
+    let is_higher = packages.get(name).is_none_or(|current| {
+        match (
+            semver::Version::parse(current),
+            semver::Version::parse(version),
+        ) {
+            (Ok(current), Ok(candidate)) => candidate > current,
+            _ => false,
+        }
+    });
+    if is_higher {
+        packages.insert(name.to_string(), version.to_string());
+    }
+}
+
 fn parse_anchor_toml_fallback(content: &str) -> ProjectVersions {
     let mut versions = ProjectVersions::default();
     if let Ok(value) = toml::from_str::<toml::Value>(content)
@@ -164,84 +1071,229 @@ fn parse_anchor_toml_fallback(content: &str) -> ProjectVersions {
             .and_then(|entry| entry.as_str())
             .map(std::string::ToString::to_string);
     }
+    with_exact_requirements(versions)
+}
+
+/// Pin the Solana and Anchor requirements to the detected versions, for
+/// sources that name an exact toolchain rather than a range.
+fn with_exact_requirements(mut versions: ProjectVersions) -> ProjectVersions {
+    versions.requirements.solana = versions
+        .solana_version
+        .as_deref()
+        .and_then(exact_version_requirement);
+    versions.requirements.anchor = versions
+        .anchor_version
+        .as_deref()
+        .and_then(exact_version_requirement);
     versions
 }
 
-fn parse_cargo_toml_fallback(content: &str) -> ProjectVersions {
-    let mut versions = ProjectVersions::default();
+fn parse_cargo_manifest_fallback(content: &str) -> CargoManifest {
+    let mut manifest = CargoManifest::default();
     if let Ok(value) = toml::from_str::<toml::Value>(content) {
-        if let Some(deps) = value.get("dependencies").and_then(|entry| entry.as_table()) {
-            update_versions_from_toml_table(&mut versions, deps);
+        if let Some(root) = value.as_table() {
+            collect_dependency_tables_from_toml_table(&mut manifest.dependencies, root, None);
+        }
+        if let Some(targets) = value.get("target").and_then(|entry| entry.as_table()) {
+            for (target, tables) in targets {
+                if let Some(tables) = tables.as_table() {
+                    collect_dependency_tables_from_toml_table(
+                        &mut manifest.dependencies,
+                        tables,
+                        Some(target),
+                    );
+                }
+            }
         }
-        if let Some(workspace) = value.get("workspace").and_then(|entry| entry.as_table())
-            && let Some(workspace_deps) = workspace
+        if let Some(rust_version) = value
+            .get("package")
+            .and_then(|package| package.get("rust-version"))
+        {
+            manifest.rust_version = rust_version.as_str().map(str::to_string);
+            manifest.inherits_rust_version = rust_version
+                .get("workspace")
+                .and_then(toml::Value::as_bool)
+                .unwrap_or(false);
+        }
+        if let Some(workspace) = value.get("workspace").and_then(|entry| entry.as_table()) {
+            manifest.is_workspace_root = true;
+            manifest.workspace_rust_version = workspace
+                .get("package")
+                .and_then(|package| package.get("rust-version"))
+                .and_then(toml::Value::as_str)
+                .map(str::to_string);
+            let strings = |key: &str| -> Vec<String> {
+                workspace
+                    .get(key)
+                    .and_then(toml::Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(toml::Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            };
+            manifest.workspace_members = strings("members");
+            manifest.workspace_exclude = strings("exclude");
+            if let Some(workspace_deps) = workspace
                 .get("dependencies")
                 .and_then(|entry| entry.as_table())
-        {
-            update_versions_from_toml_table(&mut versions, workspace_deps);
+            {
+                collect_manifest_dependencies_from_toml_table(
+                    &mut manifest.dependencies,
+                    workspace_deps,
+                    DependencyTable::Workspace,
+                    None,
+                );
+            }
         }
     }
-    versions
+    manifest
 }
 
-fn get_version_from_spec(spec: &DependencySpec) -> Option<String> {
-    match spec {
-        DependencySpec::Simple(version) => Some(parse_semver_range(version)),
-        DependencySpec::Detailed(details) => details
-            .version
-            .as_ref()
-            .map(|version| parse_semver_range(version)),
-    }
+fn repository_slug(url: &str) -> Option<String> {
+    let trimmed = url.trim().trim_end_matches('/');
+    let trimmed = trimmed.strip_suffix(".git").unwrap_or(trimmed);
+    let mut segments = trimmed
+        .rsplit(['/', ':'])
+        .filter(|segment| !segment.is_empty());
+    let name = segments.next()?;
+    let owner = segments.next()?;
+    Some(format!("{owner}/{name}"))
 }
 
-fn update_versions_from_dependencies(versions: &mut ProjectVersions, deps: &Dependencies) {
-    if versions.solana_version.is_none()
-        && let Some(solana_spec) = &deps.solana_program
-    {
-        versions.solana_version = get_version_from_spec(solana_spec);
-    }
+fn recognize<'a>(crates: &'a [RecognizedCrate], package: &str) -> Option<&'a RecognizedCrate> {
+    crates.iter().find(|recognized| recognized.matches(package))
+}
 
-    if versions.anchor_version.is_none()
-        && let Some(anchor_spec) = &deps.anchor_lang
-    {
-        versions.anchor_version = get_version_from_spec(anchor_spec);
-    }
+fn versions_from_signals(signals: &[DependencySignal]) -> ProjectVersions {
+    let solana = strongest_signal(signals, VersionField::Solana);
+    let anchor = strongest_signal(signals, VersionField::Anchor);
 
-    if versions.anchor_version.is_none()
-        && let Some(anchor_spl_spec) = &deps.anchor_spl
-    {
-        versions.anchor_version = get_version_from_spec(anchor_spl_spec);
+    ProjectVersions {
+        rust_version: None,
+        solana_version: solana.map(|signal| signal.version.clone()),
+        anchor_version: anchor.map(|signal| signal.version.clone()),
+        platform_tools_version: None,
+        requirements: VersionRequirements {
+            rust: None,
+            solana: solana.and_then(|signal| signal.requirement.clone()),
+            anchor: anchor.and_then(|signal| signal.requirement.clone()),
+        },
     }
 }
 
-fn update_versions_from_toml_table(versions: &mut ProjectVersions, deps: &toml::value::Table) {
-    if versions.solana_version.is_none() {
-        versions.solana_version = extract_version_from_toml_value(deps.get("solana-program"));
+fn collect_dependency_tables(
+    dependencies: &mut Vec<ManifestDependency>,
+    tables: &DependencyTables,
+    target: Option<&str>,
+) {
+    for (deps, table) in [
+        (&tables.dependencies, DependencyTable::Normal),
+        (&tables.dev_dependencies, DependencyTable::Dev),
+        (&tables.build_dependencies, DependencyTable::Build),
+    ] {
+        if let Some(deps) = deps {
+            collect_manifest_dependencies(dependencies, deps, table, target);
+        }
     }
+}
 
-    if versions.anchor_version.is_none() {
-        versions.anchor_version = extract_version_from_toml_value(deps.get("anchor-lang"));
+fn collect_dependency_tables_from_toml_table(
+    dependencies: &mut Vec<ManifestDependency>,
+    tables: &toml::value::Table,
+    target: Option<&str>,
+) {
+    for (key, table) in [
+        ("dependencies", DependencyTable::Normal),
+        ("dev-dependencies", DependencyTable::Dev),
+        ("build-dependencies", DependencyTable::Build),
+    ] {
+        if let Some(deps) = tables.get(key).and_then(|entry| entry.as_table()) {
+            collect_manifest_dependencies_from_toml_table(dependencies, deps, table, target);
+        }
     }
+}
 
-    if versions.anchor_version.is_none() {
-        versions.anchor_version = extract_version_from_toml_value(deps.get("anchor-spl"));
+fn collect_manifest_dependencies(
+    dependencies: &mut Vec<ManifestDependency>,
+    deps: &Dependencies,
+    table: DependencyTable,
+    target: Option<&str>,
+) {
+    for (name, spec) in deps {
+        let (package, requirement, workspace, git) = match spec {
+            DependencySpec::Simple(version) => (None, Some(version.clone()), false, None),
+            DependencySpec::Detailed(details) => (
+                details.package.as_deref(),
+                details.version.clone(),
+                details.workspace,
+                details.git.as_deref().map(|url| {
+                    parse_git_source(
+                        url,
+                        details.tag.as_deref(),
+                        details.rev.as_deref(),
+                        details.branch.as_deref(),
+                    )
+                }),
+            ),
+        };
+        dependencies.push(ManifestDependency {
+            name: package.unwrap_or(name).to_string(),
+            alias: package
+                .filter(|package| package != name)
+                .map(|_| name.clone()),
+            table,
+            target: target.map(std::string::ToString::to_string),
+            requirement,
+            workspace,
+            git,
+        });
     }
 }
 
-fn extract_version_from_toml_value(value: Option<&toml::Value>) -> Option<String> {
-    value.and_then(|entry| match entry {
-        toml::Value::String(version) => Some(parse_semver_range(version)),
-        toml::Value::Table(table) => table
-            .get("version")
-            .and_then(|version| version.as_str())
-            .map(parse_semver_range),
-        _ => None,
-    })
+fn collect_manifest_dependencies_from_toml_table(
+    dependencies: &mut Vec<ManifestDependency>,
+    deps: &toml::value::Table,
+    table: DependencyTable,
+    target: Option<&str>,
+) {
+    for (name, value) in deps {
+        let (package, requirement, workspace, git) = match value {
+            toml::Value::String(version) => (None, Some(version.clone()), false, None),
+            toml::Value::Table(entry) => {
+                let string = |key: &str| entry.get(key).and_then(|value| value.as_str());
+                (
+                    string("package"),
+                    string("version").map(std::string::ToString::to_string),
+                    entry
+                        .get("workspace")
+                        .and_then(toml::Value::as_bool)
+                        .unwrap_or(false),
+                    string("git").map(|url| {
+                        parse_git_source(url, string("tag"), string("rev"), string("branch"))
+                    }),
+                )
+            }
+            _ => continue,
+        };
+        dependencies.push(ManifestDependency {
+            name: package.unwrap_or(name).to_string(),
+            alias: package
+                .filter(|package| package != name)
+                .map(|_| name.clone()),
+            table,
+            target: target.map(std::string::ToString::to_string),
+            requirement,
+            workspace,
+            git,
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::RepositoryKind;
 
     #[test]
     fn test_parse_semver_range_with_comma_range() {
@@ -308,6 +1360,298 @@ mod tests {
         assert_eq!(versions.anchor_version.as_deref(), Some("0.30.1"));
     }
 
+    #[test]
+    fn test_parse_cargo_toml_keeps_full_requirement() {
+        let versions = parse_cargo_toml(
+            r#"
+            [dependencies]
+            solana-program = ">=1.18,<2"
+            anchor-lang = "0.30.1"
+            "#,
+        );
+
+        assert_eq!(versions.solana_version.as_deref(), Some("1.18"));
+        assert_eq!(
+            versions.requirements.solana,
+            Some(VersionReq::parse(">=1.18, <2").unwrap())
+        );
+        assert_eq!(
+            versions.requirements.anchor,
+            Some(VersionReq::parse("^0.30.1").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_lockfile_and_anchor_toml_pin_exact_requirements() {
+        let locked = parse_cargo_lock(
+            r#"
+            [[package]]
+            name = "anchor-lang"
+            version = "0.30.1"
+            "#,
+        );
+        assert_eq!(
+            locked.requirements.anchor,
+            Some(VersionReq::parse("=0.30.1").unwrap())
+        );
+
+        let toolchain = parse_anchor_toml("[toolchain]\nsolana_version = \"1.18.17\"\n");
+        assert_eq!(
+            toolchain.requirements.solana,
+            Some(VersionReq::parse("=1.18.17").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_cargo_toml_recognizes_split_and_agave_crates() {
+        let versions = parse_cargo_toml(
+            r#"
+            [dependencies]
+            solana-pubkey = "2.2.1"
+            agave-feature-set = "2.2.14"
+            "#,
+        );
+
+        assert_eq!(versions.solana_version.as_deref(), Some("2.2.14"));
+        assert_eq!(versions.anchor_version, None);
+    }
+
+    #[test]
+    fn test_parse_cargo_dependencies_records_crate_name_and_weight() {
+        let signals = parse_cargo_dependencies(
+            r#"
+            [dependencies]
+            solana-sdk = "1.18.17"
+            solana-program = "1.18.26"
+            "#,
+            recognized_crates(),
+        );
+
+        let strongest = strongest_signal(&signals, VersionField::Solana).unwrap();
+        assert_eq!(strongest.origin.crate_name, "solana-program");
+        assert_eq!(strongest.version, "1.18.26");
+        assert_eq!(signals.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_cargo_dependencies_fallback_uses_recognized_crates() {
+        let signals = parse_cargo_dependencies(
+            r#"
+            [dependencies]
+            solana-client = { version = "1.17.3" }
+            weird = 5
+            "#,
+            recognized_crates(),
+        );
+
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].origin.crate_name, "solana-client");
+        assert_eq!(signals[0].version, "1.17.3");
+    }
+
+    #[test]
+    fn test_parse_cargo_manifest_marks_workspace_inherited_dependencies() {
+        let manifest = parse_cargo_manifest(
+            r#"
+            [dependencies]
+            anchor-lang = { workspace = true, features = ["init-if-needed"] }
+            "#,
+        );
+
+        assert!(!manifest.is_workspace_root);
+        assert_eq!(
+            manifest.dependencies,
+            vec![ManifestDependency {
+                name: "anchor-lang".to_string(),
+                alias: None,
+                table: DependencyTable::Normal,
+                target: None,
+                requirement: None,
+                workspace: true,
+                git: None,
+            }]
+        );
+        assert!(dependency_signals(&manifest.dependencies, recognized_crates()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_cargo_manifest_reads_dev_build_and_target_tables() {
+        let manifest = parse_cargo_manifest(
+            r#"
+            [dev-dependencies]
+            solana-program-test = "1.18.17"
+
+            [build-dependencies]
+            anchor-lang = "0.30.1"
+
+            [target.'cfg(target_os = "solana")'.dependencies]
+            solana-program = "1.18.26"
+            "#,
+        );
+
+        let tables: Vec<_> = manifest
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency.table, dependency.target.as_deref()))
+            .collect();
+        assert_eq!(
+            tables,
+            vec![
+                (DependencyTable::Dev, None),
+                (DependencyTable::Build, None),
+                (DependencyTable::Normal, Some("cfg(target_os = \"solana\")")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strongest_signal_prefers_normal_over_dev_dependencies() {
+        let signals = parse_cargo_dependencies(
+            r#"
+            [dependencies]
+            solana-pubkey = "2.2.1"
+
+            [dev-dependencies]
+            solana-program-test = "2.1.0"
+            "#,
+            recognized_crates(),
+        );
+
+        let strongest = strongest_signal(&signals, VersionField::Solana).unwrap();
+        assert_eq!(strongest.origin.crate_name, "solana-pubkey");
+        assert_eq!(strongest.origin.table, Some(DependencyTable::Normal));
+    }
+
+    #[test]
+    fn test_parse_cargo_dependencies_fallback_reads_dev_dependencies() {
+        let signals = parse_cargo_dependencies(
+            r#"
+            weird = 5
+
+            [dev-dependencies]
+            anchor-lang = "0.29.0"
+            "#,
+            recognized_crates(),
+        );
+
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].origin.table, Some(DependencyTable::Dev));
+    }
+
+    #[test]
+    fn test_git_dependency_tag_maps_to_release() {
+        let signals = parse_cargo_dependencies(
+            r#"
+            [dependencies]
+            anchor-lang = { git = "https://github.com/coral-xyz/anchor", tag = "v0.29.0" }
+            "#,
+            recognized_crates(),
+        );
+
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].version, "0.29.0");
+        let git = signals[0].origin.git.as_ref().unwrap();
+        assert_eq!(git.repository.as_deref(), Some("coral-xyz/anchor"));
+        assert_eq!(git.repository_kind, Some(RepositoryKind::Upstream));
+    }
+
+    #[test]
+    fn test_git_dependency_rev_is_recorded_without_version() {
+        let manifest = parse_cargo_manifest(
+            r#"
+            [dependencies]
+            anchor-lang = { git = "git@github.com:otter-sec/anchor.git", rev = "4f1a2b3" }
+            "#,
+        );
+
+        let git = manifest.dependencies[0].git.as_ref().unwrap();
+        assert_eq!(git.repository.as_deref(), Some("otter-sec/anchor"));
+        assert_eq!(git.repository_kind, Some(RepositoryKind::Fork));
+        assert_eq!(git.rev.as_deref(), Some("4f1a2b3"));
+        let signals = dependency_signals(&manifest.dependencies, recognized_crates());
+        assert_eq!(signals[0].version, "*");
+        assert_eq!(signals[0].requirement, None);
+    }
+
+    #[test]
+    fn test_renamed_dependency_is_indexed_by_package_name() {
+        let signals = parse_cargo_dependencies(
+            r#"
+            [dependencies]
+            anchor = { package = "anchor-lang", version = "0.30.1" }
+            solana = { package = "solana-program", version = "1.18.17" }
+            "#,
+            recognized_crates(),
+        );
+
+        let anchor = strongest_signal(&signals, VersionField::Anchor).unwrap();
+        assert_eq!(anchor.version, "0.30.1");
+        assert_eq!(anchor.origin.crate_name, "anchor-lang");
+        assert_eq!(anchor.origin.alias.as_deref(), Some("anchor"));
+
+        let solana = strongest_signal(&signals, VersionField::Solana).unwrap();
+        assert_eq!(solana.origin.crate_name, "solana-program");
+        assert_eq!(solana.origin.alias.as_deref(), Some("solana"));
+    }
+
+    #[test]
+    fn test_renamed_dependency_is_indexed_by_package_name_in_fallback() {
+        let manifest = parse_cargo_manifest(
+            r#"
+            weird = 5
+
+            [dependencies]
+            anchor = { package = "anchor-lang", version = "0.29.0" }
+            "#,
+        );
+
+        assert_eq!(manifest.dependencies[0].name, "anchor-lang");
+        assert_eq!(manifest.dependencies[0].alias.as_deref(), Some("anchor"));
+        assert_eq!(manifest.dependencies[0].key(), "anchor");
+    }
+
+    #[test]
+    fn test_parse_cargo_lock_reports_highest_locked_version() {
+        let versions = parse_cargo_lock(
+            r#"
+            version = 3
+
+            [[package]]
+            name = "solana-program"
+            version = "1.18.26"
+
+            [[package]]
+            name = "solana-program"
+            version = "1.17.3"
+
+            [[package]]
+            name = "anchor-spl"
+            version = "0.30.1"
+            "#,
+        );
+
+        assert_eq!(versions.solana_version.as_deref(), Some("1.18.26"));
+        assert_eq!(versions.anchor_version.as_deref(), Some("0.30.1"));
+        assert_eq!(versions.rust_version, None);
+    }
+
+    #[test]
+    fn test_parse_cargo_lock_prefers_anchor_lang_over_anchor_spl() {
+        let versions = parse_cargo_lock(
+            r#"
+            [[package]]
+            name = "anchor-spl"
+            version = "0.31.0"
+
+            [[package]]
+            name = "anchor-lang"
+            version = "0.30.1"
+            "#,
+        );
+
+        assert_eq!(versions.anchor_version.as_deref(), Some("0.30.1"));
+    }
+
     #[test]
     fn test_parse_anchor_toml_extracts_toolchain_versions() {
         let versions = parse_anchor_toml(
@@ -322,6 +1666,227 @@ mod tests {
         assert_eq!(versions.anchor_version.as_deref(), Some("0.30.1"));
     }
 
+    #[test]
+    fn test_parse_anchor_config_reads_all_sections() {
+        let config = parse_anchor_config(
+            r#"
+            [toolchain]
+            anchor_version = "0.30.1"
+            package_manager = "yarn"
+
+            [features]
+            resolution = true
+            skip-lint = false
+
+            [workspace]
+            members = ["programs/*"]
+            exclude = ["programs/legacy"]
+
+            [programs.localnet]
+            counter = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"
+
+            [programs.devnet]
+            counter = { address = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS", idl = "target/idl/counter.json" }
+
+            [provider]
+            cluster = "Localnet"
+            wallet = "~/.config/solana/id.json"
+
+            [scripts]
+            test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
+
+            [test]
+            startup_wait = 5000
+
+            [[test.genesis]]
+            address = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+            program = "tests/metaplex.so"
+
+            [test.validator]
+            url = "https://api.mainnet-beta.solana.com"
+            rpc_port = 8899
+
+            [[test.validator.clone]]
+            address = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+
+            [[test.validator.account]]
+            address = "3vMPj13emX9JmifYcWc77ekEzV1F37ga36E1YeSr6Mdj"
+            filename = "tests/accounts/config.json"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.toolchain.package_manager.as_deref(), Some("yarn"));
+        assert_eq!(config.features.resolution, Some(true));
+        assert_eq!(config.features.skip_lint, Some(false));
+        assert_eq!(config.workspace.members, vec!["programs/*"]);
+        assert_eq!(config.workspace.exclude, vec!["programs/legacy"]);
+        assert_eq!(config.programs["localnet"]["counter"].idl, None);
+        assert_eq!(
+            config.programs["devnet"]["counter"].idl.as_deref(),
+            Some("target/idl/counter.json")
+        );
+        assert_eq!(config.provider.cluster.as_deref(), Some("Localnet"));
+        assert!(config.scripts["test"].starts_with("yarn run ts-mocha"));
+
+        let test = config.test.unwrap();
+        assert_eq!(test.startup_wait, Some(5000));
+        assert_eq!(test.genesis[0].program, "tests/metaplex.so");
+        let validator = test.validator.unwrap();
+        assert_eq!(validator.rpc_port, Some(8899));
+        assert_eq!(
+            validator.clone,
+            vec!["metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"]
+        );
+        assert_eq!(validator.account[0].filename, "tests/accounts/config.json");
+    }
+
+    #[test]
+    fn test_parse_anchor_config_rejects_malformed_sections() {
+        assert!(parse_anchor_config("[features]\nresolution = \"yes\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_tool_versions() {
+        let versions = parse_tool_versions(
+            "# pinned tools\nrust 1.79.0\nsolana 1.18.26 1.18.17\nanchor v0.30.1\nnodejs 20.11.0\n",
+        );
+
+        assert_eq!(versions.rust_version.as_deref(), Some("1.79.0"));
+        assert_eq!(versions.solana_version.as_deref(), Some("1.18.26"));
+        assert_eq!(versions.anchor_version.as_deref(), Some("0.30.1"));
+        assert_eq!(
+            versions.requirements.anchor,
+            Some(VersionReq::parse("=0.30.1").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_tool_versions_skips_unpinned_entries() {
+        let versions =
+            parse_tool_versions("solana system\nanchor latest\nrust nightly-2024-02-01\n");
+
+        assert_eq!(versions.solana_version, None);
+        assert_eq!(versions.anchor_version, None);
+        assert_eq!(versions.rust_version.as_deref(), Some("nightly-2024-02-01"));
+    }
+
+    #[test]
+    fn test_parse_mise_toml() {
+        let versions = parse_mise_toml(
+            r#"
+            [tools]
+            rust = { version = "1.79.0", components = "clippy" }
+            "ubi:anza-xyz/agave" = ["2.1.0", "2.0.0"]
+            "cargo:anchor-cli" = "0.31.0"
+            node = "20"
+            "#,
+        );
+
+        assert_eq!(versions.rust_version.as_deref(), Some("1.79.0"));
+        assert_eq!(versions.solana_version.as_deref(), Some("2.1.0"));
+        assert_eq!(versions.anchor_version.as_deref(), Some("0.31.0"));
+    }
+
+    #[test]
+    fn test_parse_idl_formats() {
+        assert_eq!(
+            parse_idl(
+                r#"{"address": "Fg6P", "metadata": {"name": "counter", "version": "0.1.0", "spec": "0.1.0"}, "instructions": []}"#
+            )
+            .unwrap(),
+            IdlFormat::Spec("0.1.0".to_string())
+        );
+        assert_eq!(
+            parse_idl(r#"{"version": "0.1.0", "name": "counter", "instructions": []}"#).unwrap(),
+            IdlFormat::Legacy
+        );
+        assert!(parse_idl(r#"{"name": "package"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_package_json_prefers_dependencies() {
+        let packages = parse_package_json(
+            r#"{
+                "dependencies": { "@coral-xyz/anchor": "^0.30.1" },
+                "devDependencies": { "@coral-xyz/anchor": "^0.29.0", "@solana/web3.js": "1.95.3" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(packages["@coral-xyz/anchor"], "^0.30.1");
+        assert_eq!(packages["@solana/web3.js"], "1.95.3");
+    }
+
+    #[test]
+    fn test_parse_yarn_lock_classic_and_berry() {
+        let direct =
+            |name: &str, range: &str| BTreeMap::from([(name.to_string(), range.to_string())]);
+        let classic_lock = "# yarn lockfile v1\n\n\"@coral-xyz/anchor@^0.30.0\", \"@coral-xyz/anchor@^0.30.1\":\n  version \"0.30.1\"\n  dependencies:\n    bn.js \"^5.1.2\"\n\n\"@coral-xyz/anchor@^0.29.0\":\n  version \"0.29.0\"\n";
+        let classic = parse_yarn_lock(classic_lock, &direct("@coral-xyz/anchor", "^0.30.1"));
+        assert_eq!(classic["@coral-xyz/anchor"], "0.30.1");
+        let classic = parse_yarn_lock(classic_lock, &direct("@coral-xyz/anchor", "^0.29.0"));
+        assert_eq!(classic["@coral-xyz/anchor"], "0.29.0");
+
+        let berry = parse_yarn_lock(
+            "__metadata:\n  version: 6\n\n\"@solana/web3.js@npm:^1.95.0\":\n  version: 1.95.3\n  resolution: \"@solana/web3.js@npm:1.95.3\"\n",
+            &direct("@solana/web3.js", "^1.95.0"),
+        );
+        assert_eq!(berry["@solana/web3.js"], "1.95.3");
+        assert!(!berry.contains_key("__metadata"));
+    }
+
+    #[test]
+    fn test_parse_package_lock_versions() {
+        let packages = parse_package_lock(
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "name": "app" },
+                    "node_modules/@coral-xyz/anchor": { "version": "0.30.1" },
+                    "node_modules/foo/node_modules/@coral-xyz/anchor": { "version": "0.29.0" }
+                }
+            }"#,
+        );
+
+        assert_eq!(packages["@coral-xyz/anchor"], "0.30.1");
+        assert!(!packages.contains_key(""));
+    }
+
+    #[test]
+    fn test_parse_package_lock_ignores_newer_nested_copy() {
+        let packages = parse_package_lock(
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "name": "app" },
+                    "node_modules/@coral-xyz/anchor": { "version": "0.29.0" },
+                    "node_modules/some-sdk/node_modules/@coral-xyz/anchor": { "version": "0.31.0" }
+                }
+            }"#,
+        );
+
+        assert_eq!(packages["@coral-xyz/anchor"], "0.29.0");
+        assert!(!packages.contains_key("some-sdk/node_modules/@coral-xyz/anchor"));
+    }
+
+    #[test]
+    fn test_parse_pnpm_lock_key_formats() {
+        let packages = parse_pnpm_lock(
+            "lockfileVersion: '9.0'\n\nimporters:\n  .:\n    dependencies:\n      '@coral-xyz/anchor':\n        version: 0.31.0\n\npackages:\n\n  '@coral-xyz/anchor@0.30.1':\n    resolution: {integrity: sha512-x}\n\n  /@solana/web3.js/1.95.3:\n    resolution: {integrity: sha512-y}\n\n  /@project-serum/anchor@0.26.0(typescript@5.0.0):\n    dependencies: {}\n",
+        );
+
+        assert_eq!(packages["@coral-xyz/anchor"], "0.30.1");
+        assert_eq!(packages["@solana/web3.js"], "1.95.3");
+        assert_eq!(packages["@project-serum/anchor"], "0.26.0");
+    }
+
+    #[test]
+    fn test_parse_anchor_version_file() {
+        assert_eq!(parse_anchor_version_file("v0.30.1\n").unwrap(), "0.30.1");
+        assert!(parse_anchor_version_file("latest").is_err());
+    }
+
     #[test]
     fn test_parse_rust_toolchain_plain_text() {
         assert_eq!(
@@ -330,6 +1895,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_rust_toolchain_spec_keeps_components_and_host() {
+        let spec = parse_rust_toolchain_spec(
+            r#"
+            [toolchain]
+            channel = "nightly-2024-02-01-x86_64-unknown-linux-gnu"
+            components = ["rust-analyzer", "clippy", "rustfmt"]
+            targets = ["wasm32-unknown-unknown"]
+            profile = "minimal"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            spec.channel,
+            Some(ToolchainChannel::Nightly {
+                date: Some("2024-02-01".to_string())
+            })
+        );
+        assert_eq!(spec.host.as_deref(), Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(spec.components, vec!["rust-analyzer", "clippy", "rustfmt"]);
+        assert_eq!(spec.targets, vec!["wasm32-unknown-unknown"]);
+        assert_eq!(spec.profile.as_deref(), Some("minimal"));
+        assert_eq!(
+            spec.toolchain_name().as_deref(),
+            Some("nightly-2024-02-01-x86_64-unknown-linux-gnu")
+        );
+    }
+
+    #[test]
+    fn test_parse_rust_toolchain_spec_channels() {
+        let channel = |content: &str| parse_rust_toolchain_spec(content).unwrap().channel;
+
+        assert_eq!(channel("stable"), Some(ToolchainChannel::Stable));
+        assert_eq!(channel("beta"), Some(ToolchainChannel::Beta { date: None }));
+        assert_eq!(
+            channel("1.76.0-aarch64-apple-darwin"),
+            Some(ToolchainChannel::Version("1.76.0".to_string()))
+        );
+        assert_eq!(
+            channel("[toolchain]\nchannel = \"solana-1.75\""),
+            Some(ToolchainChannel::Custom("solana-1.75".to_string()))
+        );
+        assert!(parse_rust_toolchain_spec("not a toolchain").is_err());
+    }
+
+    #[test]
+    fn test_parse_rust_toolchain_spec_path_only() {
+        let spec = parse_rust_toolchain_spec("[toolchain]\npath = \"/opt/rust\"").unwrap();
+
+        assert_eq!(spec.channel, None);
+        assert_eq!(spec.path, Some(PathBuf::from("/opt/rust")));
+        assert!(parse_rust_toolchain("[toolchain]\npath = \"/opt/rust\"").is_err());
+    }
+
     #[test]
     fn test_detailed_dependency_other_fields_are_ignored() {
         let details =
@@ -339,4 +1959,27 @@ mod tests {
         assert_eq!(details.version.as_deref(), Some(">=1.18,<=2"));
         assert!(details._other.contains_key("features"));
     }
+
+    #[test]
+    fn test_requirements_overlap_compares_lower_bounds() {
+        let requirement = |value: &str| VersionReq::parse(value).unwrap();
+
+        assert_eq!(
+            requirement_lower_bound(&requirement(">0.30, <0.32")),
+            Some(Version::new(0, 31, 0))
+        );
+        assert_eq!(requirement_lower_bound(&requirement("<0.32")), None);
+        assert!(requirements_overlap(
+            &requirement(">=2.1, <2.2"),
+            &requirement("^2.1.7")
+        ));
+        assert!(requirements_overlap(
+            &requirement("<2.2"),
+            &requirement("=1.18.26")
+        ));
+        assert!(!requirements_overlap(
+            &requirement(">=2.1, <2.2"),
+            &requirement("=2.2.0")
+        ));
+    }
 }
@@ -0,0 +1,173 @@
+use semver::Version;
+
+use crate::types::{RustRelease, ToolchainChannel};
+
+const fn release(version: &'static str, date: &'static str) -> RustRelease {
+    RustRelease { version, date }
+}
+
+/// Stable Rust releases and the dates they shipped, oldest first.
+///
+/// Releases follow a six-week train, so a dated nightly or beta can be placed
+/// on it without network access.
+const RUST_RELEASES: [RustRelease; 40] = [
+    release("1.60.0", "2022-04-07"),
+    release("1.61.0", "2022-05-19"),
+    release("1.62.0", "2022-06-30"),
+    release("1.63.0", "2022-08-11"),
+    release("1.64.0", "2022-09-22"),
+    release("1.65.0", "2022-11-03"),
+    release("1.66.0", "2022-12-15"),
+    release("1.67.0", "2023-01-26"),
+    release("1.68.0", "2023-03-09"),
+    release("1.69.0", "2023-04-20"),
+    release("1.70.0", "2023-06-01"),
+    release("1.71.0", "2023-07-13"),
+    release("1.72.0", "2023-08-24"),
+    release("1.73.0", "2023-10-05"),
+    release("1.74.0", "2023-11-16"),
+    release("1.75.0", "2023-12-28"),
+    release("1.76.0", "2024-02-08"),
+    release("1.77.0", "2024-03-21"),
+    release("1.78.0", "2024-05-02"),
+    release("1.79.0", "2024-06-13"),
+    release("1.80.0", "2024-07-25"),
+    release("1.81.0", "2024-09-05"),
+    release("1.82.0", "2024-10-17"),
+    release("1.83.0", "2024-11-28"),
+    release("1.84.0", "2025-01-09"),
+    release("1.85.0", "2025-02-20"),
+    release("1.86.0", "2025-04-03"),
+    release("1.87.0", "2025-05-15"),
+    release("1.88.0", "2025-06-26"),
+    release("1.89.0", "2025-08-07"),
+    release("1.90.0", "2025-09-18"),
+    release("1.91.0", "2025-10-30"),
+    release("1.92.0", "2025-12-11"),
+    release("1.93.0", "2026-01-22"),
+    release("1.94.0", "2026-03-05"),
+    release("1.95.0", "2026-04-16"),
+    release("1.96.0", "2026-05-28"),
+    release("1.97.0", "2026-07-09"),
+    release("1.98.0", "2026-08-20"),
+    release("1.99.0", "2026-10-01"),
+];
+
+/// Days between two stable releases.
+const RELEASE_CADENCE_DAYS: u32 = 42;
+
+#[must_use]
+pub const fn rust_releases() -> &'static [RustRelease] {
+    &RUST_RELEASES
+}
+
+/// The stable Rust release a toolchain channel corresponds to.
+///
+/// A nightly built on a given date becomes the release two trains after the
+/// latest stable shipped on or before that date, and a beta becomes the next
+/// one. Explicit versions map to themselves. Undated channels and dates
+/// outside the table return `None`.
+#[must_use]
+pub fn stable_equivalent(channel: &ToolchainChannel) -> Option<Version> {
+    let (date, trains_ahead) = match channel {
+        ToolchainChannel::Version(version) => return parse_release(version),
+        ToolchainChannel::Nightly { date: Some(date) } => (date, 2),
+        ToolchainChannel::Beta { date: Some(date) } => (date, 1),
+        _ => return None,
+    };
+
+    let latest = RUST_RELEASES
+        .iter()
+        .rev()
+        .find(|release| release.date <= date.as_str())?;
+    if latest == RUST_RELEASES.last()? && days_since(latest.date, date)? > RELEASE_CADENCE_DAYS {
+        return None;
+    }
+
+    let stable = Version::parse(latest.version).ok()?;
+    Some(Version::new(
+        stable.major,
+        stable.minor.checked_add(trains_ahead)?,
+        0,
+    ))
+}
+
+fn parse_release(version: &str) -> Option<Version> {
+    Version::parse(version).ok().or_else(|| {
+        let (major, minor) = version.split_once('.')?;
+        Some(Version::new(major.parse().ok()?, minor.parse().ok()?, 0))
+    })
+}
+
+/// Whole days from `start` to `end`, both `YYYY-MM-DD`.
+fn days_since(start: &str, end: &str) -> Option<u32> {
+    day_number(end)?.checked_sub(day_number(start)?)
+}
+
+/// Days since 0000-03-01 in the proleptic Gregorian calendar.
+fn day_number(date: &str) -> Option<u32> {
+    let mut parts = date.splitn(3, '-');
+    let year: u32 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+
+    let (year, month) = if month > 2 {
+        (year, month.checked_sub(3)?)
+    } else {
+        (year.checked_sub(1)?, month.checked_add(9)?)
+    };
+    let year_days = year
+        .checked_mul(365)?
+        .checked_add(year / 4)?
+        .checked_sub(year / 100)?
+        .checked_add(year / 400)?;
+    let month_days = month.checked_mul(153)?.checked_add(2)? / 5;
+    year_days.checked_add(month_days)?.checked_add(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nightly(date: &str) -> ToolchainChannel {
+        ToolchainChannel::Nightly {
+            date: Some(date.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_nightly_maps_two_trains_ahead() {
+        assert_eq!(
+            stable_equivalent(&nightly("2023-10-29")),
+            Some(Version::new(1, 75, 0))
+        );
+        assert_eq!(
+            stable_equivalent(&nightly("2024-02-08")),
+            Some(Version::new(1, 78, 0))
+        );
+    }
+
+    #[test]
+    fn test_beta_maps_to_next_release() {
+        let beta = ToolchainChannel::Beta {
+            date: Some("2024-02-10".to_string()),
+        };
+        assert_eq!(stable_equivalent(&beta), Some(Version::new(1, 77, 0)));
+    }
+
+    #[test]
+    fn test_dates_outside_table_are_not_mapped() {
+        assert_eq!(stable_equivalent(&nightly("2021-12-01")), None);
+        assert_eq!(stable_equivalent(&nightly("2030-01-01")), None);
+        assert_eq!(
+            stable_equivalent(&ToolchainChannel::Nightly { date: None }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_day_number_counts_leap_days() {
+        assert_eq!(days_since("2024-02-28", "2024-03-01"), Some(2));
+        assert_eq!(days_since("2026-10-01", "2026-11-12"), Some(42));
+    }
+}
@@ -0,0 +1,213 @@
+use semver::{Version, VersionReq};
+
+use crate::parse::{requirement_upper_bound, requirements_overlap};
+use crate::types::{
+    CompatibilityMatrix, CompatibilityRule, ConstraintSolution, FeasibleCombination,
+    UnsatisfiableCore, VersionConstraint, VersionField, VersionSource,
+};
+
+/// Collect the distinct requirements recorded in `sources`.
+#[must_use]
+pub fn constraints_from_sources(sources: &[VersionSource]) -> Vec<VersionConstraint> {
+    let mut constraints: Vec<VersionConstraint> = Vec::new();
+    for source in sources {
+        let Some(requirement) = &source.requirement else {
+            continue;
+        };
+        let constraint = VersionConstraint {
+            field: source.field,
+            requirement: requirement.clone(),
+            kind: source.kind,
+            path: source.path.clone(),
+        };
+        if !constraints.contains(&constraint) {
+            constraints.push(constraint);
+        }
+    }
+    constraints
+}
+
+/// Intersect every requirement in `sources` with the built-in compatibility
+/// matrix.
+///
+/// See [`CompatibilityMatrix::solve_constraints`].
+#[must_use]
+pub fn solve_constraints(sources: &[VersionSource]) -> ConstraintSolution {
+    CompatibilityMatrix::built_in().solve_constraints(sources)
+}
+
+impl CompatibilityMatrix {
+    /// Intersect every requirement in `sources` with this matrix.
+    ///
+    /// A rule satisfies a constraint when its recommended version does, or when
+    /// its tested range overlaps it. Feasible combinations are ranked by how
+    /// many constraints the recommended versions satisfy directly, then by
+    /// matrix order (newest Anchor first). When no rule fits, the result names
+    /// a minimal set of conflicting constraints.
+    #[must_use]
+    pub fn solve_constraints(&self, sources: &[VersionSource]) -> ConstraintSolution {
+        let constraints = constraints_from_sources(sources);
+
+        let mut feasible: Vec<FeasibleCombination> = self
+            .rules
+            .iter()
+            .filter(|rule| satisfies_all(rule, &constraints))
+            .map(|rule| FeasibleCombination {
+                rule: rule.clone(),
+                exact_matches: constraints
+                    .iter()
+                    .filter(|constraint| {
+                        rule_version(rule, constraint.field)
+                            .is_some_and(|version| constraint.requirement.matches(&version))
+                    })
+                    .count(),
+            })
+            .collect();
+
+        if feasible.is_empty() {
+            return ConstraintSolution::Unsatisfiable(UnsatisfiableCore {
+                constraints: self.minimal_core(constraints),
+            });
+        }
+
+        feasible.sort_by_key(|combination| std::cmp::Reverse(combination.exact_matches));
+        ConstraintSolution::Feasible(feasible)
+    }
+
+    /// Shrink an unsatisfiable set by dropping every constraint that is not
+    /// needed to keep it unsatisfiable.
+    fn minimal_core(&self, mut core: Vec<VersionConstraint>) -> Vec<VersionConstraint> {
+        let mut index = 0;
+        while index < core.len() {
+            let candidate: Vec<VersionConstraint> = core
+                .iter()
+                .enumerate()
+                .filter(|(position, _)| *position != index)
+                .map(|(_, constraint)| constraint.clone())
+                .collect();
+
+            if self.is_satisfiable(&candidate) {
+                index = index.saturating_add(1);
+            } else {
+                core = candidate;
+            }
+        }
+        core
+    }
+
+    fn is_satisfiable(&self, constraints: &[VersionConstraint]) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| satisfies_all(rule, constraints))
+    }
+}
+
+impl CompatibilityRule {
+    /// Whether this rule works with `requirement` on `field`: its recommended
+    /// or maximum tested version satisfies it, as a later patch of the same
+    /// `major.minor` if need be, or its tested range overlaps it.
+    #[must_use]
+    pub fn satisfies(&self, field: VersionField, requirement: &VersionReq) -> bool {
+        let tested = self.tested.get(field);
+        rule_version(self, field)
+            .into_iter()
+            .chain(requirement_upper_bound(tested))
+            .any(|version| satisfies(&version, requirement))
+            || requirements_overlap(tested, requirement)
+    }
+}
+
+fn satisfies_all(rule: &CompatibilityRule, constraints: &[VersionConstraint]) -> bool {
+    constraints
+        .iter()
+        .all(|constraint| rule.satisfies(constraint.field, &constraint.requirement))
+}
+
+fn rule_version(rule: &CompatibilityRule, field: VersionField) -> Option<Version> {
+    Version::parse(rule.version(field)).ok()
+}
+
+/// Whether a rule version satisfies `requirement`, letting a rule cover later
+/// patches of its `major.minor` release the same way rule lookups do. A rule
+/// for `1.18.17` therefore satisfies `=1.18.26` and `^1.18.26`.
+fn satisfies(version: &Version, requirement: &VersionReq) -> bool {
+    requirement.matches(version)
+        || requirement.comparators.iter().any(|comparator| {
+            comparator.major == version.major
+                && comparator.minor == Some(version.minor)
+                && comparator.patch.is_some_and(|patch| patch >= version.patch)
+                && requirement.matches(&Version::new(
+                    version.major,
+                    version.minor,
+                    comparator.patch.unwrap_or(version.patch),
+                ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VersionSourceKind;
+    use std::path::PathBuf;
+
+    fn source(field: VersionField, requirement: &str, path: &str) -> VersionSource {
+        VersionSource {
+            field,
+            kind: VersionSourceKind::CargoToml,
+            path: PathBuf::from(path),
+            value: requirement.to_string(),
+            requirement: Some(VersionReq::parse(requirement).unwrap()),
+            dependency: None,
+        }
+    }
+
+    #[test]
+    fn test_feasible_combinations_satisfy_every_manifest() {
+        let sources = vec![
+            source(VersionField::Solana, ">=1.17, <2", "a/Cargo.toml"),
+            source(VersionField::Anchor, "^0.30.0", "b/Cargo.toml"),
+        ];
+
+        let ConstraintSolution::Feasible(feasible) = solve_constraints(&sources) else {
+            panic!("expected a feasible solution");
+        };
+        let anchors: Vec<_> = feasible
+            .iter()
+            .map(|combination| combination.rule.anchor.as_str())
+            .collect();
+        assert_eq!(anchors, vec!["0.30.1", "0.30.0"]);
+    }
+
+    #[test]
+    fn test_exact_pin_is_covered_by_earlier_patch_rule() {
+        let sources = vec![source(VersionField::Solana, "=1.18.26", "Cargo.lock")];
+
+        let ConstraintSolution::Feasible(feasible) = solve_constraints(&sources) else {
+            panic!("expected a feasible solution");
+        };
+        assert_eq!(feasible[0].rule.solana, "1.18.17");
+    }
+
+    #[test]
+    fn test_unsatisfiable_core_names_conflicting_manifests() {
+        let sources = vec![
+            source(VersionField::Anchor, "^0.29.0", "programs/a/Cargo.toml"),
+            source(VersionField::Solana, ">=1.16", "Cargo.toml"),
+            source(VersionField::Solana, "=2.1.0", "programs/b/Cargo.toml"),
+        ];
+
+        let ConstraintSolution::Unsatisfiable(core) = solve_constraints(&sources) else {
+            panic!("expected an unsatisfiable core");
+        };
+        let paths: Vec<_> = core
+            .constraints
+            .iter()
+            .map(|constraint| constraint.path.display().to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["programs/a/Cargo.toml", "programs/b/Cargo.toml"]
+        );
+        assert!(core.to_string().contains("programs/b/Cargo.toml"));
+    }
+}
@@ -0,0 +1,268 @@
+use std::path::Path;
+
+use crate::types::{PinnedTool, TextSignal, TextSignalPattern, VersionSourceKind};
+
+const fn pattern(tool: PinnedTool, marker: &'static str) -> TextSignalPattern {
+    TextSignalPattern { tool, marker }
+}
+
+/// Markers that precede a toolchain version in CI workflows, Dockerfiles and
+/// build scripts.
+///
+/// A marker only matches as a whole key: when it starts with a key character
+/// it must not follow one, and when it ends with one it must be followed by
+/// `=` or `:` (or whitespace, for a `--flag`). `MIN_RUST_VERSION=` and
+/// `node-toolchain:` therefore do not match `RUST_VERSION` or `toolchain:`.
+/// The version is read from the text right after the marker, skipping
+/// separators, quotes and a leading `v`. Values that do not start with a
+/// digit, such as `stable` or `${{ env.SOLANA_VERSION }}`, are ignored.
+const TEXT_SIGNAL_PATTERNS: [TextSignalPattern; 25] = [
+    pattern(PinnedTool::Rust, "dtolnay/rust-toolchain@"),
+    pattern(PinnedTool::Rust, "rust-version:"),
+    pattern(PinnedTool::Rust, "toolchain:"),
+    pattern(PinnedTool::Rust, "RUST_VERSION"),
+    pattern(PinnedTool::Rust, "rustup default "),
+    pattern(PinnedTool::Rust, "rustup toolchain install "),
+    pattern(PinnedTool::Rust, "FROM rust:"),
+    pattern(PinnedTool::Rust, "image: rust:"),
+    pattern(PinnedTool::Solana, "solana-version:"),
+    pattern(PinnedTool::Solana, "solana_version:"),
+    pattern(PinnedTool::Solana, "SOLANA_VERSION"),
+    pattern(PinnedTool::Solana, "AGAVE_VERSION"),
+    pattern(PinnedTool::Solana, "release.solana.com/"),
+    pattern(PinnedTool::Solana, "release.anza.xyz/"),
+    pattern(PinnedTool::Solana, "solana-install init "),
+    pattern(PinnedTool::Solana, "agave-install init "),
+    pattern(PinnedTool::Anchor, "anchor-version:"),
+    pattern(PinnedTool::Anchor, "anchor_version:"),
+    pattern(PinnedTool::Anchor, "ANCHOR_VERSION"),
+    pattern(PinnedTool::Anchor, "avm install "),
+    pattern(PinnedTool::Anchor, "avm use "),
+    pattern(PinnedTool::Anchor, "/anchor --tag "),
+    pattern(PinnedTool::Anchor, "anchor-cli@"),
+    pattern(PinnedTool::PlatformTools, "--tools-version"),
+    pattern(PinnedTool::PlatformTools, "PLATFORM_TOOLS_VERSION"),
+];
+
+#[must_use]
+pub const fn text_signal_patterns() -> &'static [TextSignalPattern] {
+    &TEXT_SIGNAL_PATTERNS
+}
+
+/// The source kind for a file that may contain text signals, judged by its
+/// path relative to the scanned directory. Returns `None` for other files.
+#[must_use]
+pub fn text_signal_kind(relative_path: &Path) -> Option<VersionSourceKind> {
+    let file_name = relative_path.file_name()?.to_str()?;
+    let in_workflows = relative_path.parent() == Some(Path::new(".github/workflows"));
+
+    if (in_workflows
+        && Path::new(file_name)
+            .extension()
+            .is_some_and(|extension| extension == "yml" || extension == "yaml"))
+        || file_name == ".gitlab-ci.yml"
+    {
+        Some(VersionSourceKind::CiWorkflow)
+    } else if file_name == "Dockerfile"
+        || file_name.starts_with("Dockerfile.")
+        || file_name.ends_with(".Dockerfile")
+    {
+        Some(VersionSourceKind::Dockerfile)
+    } else if matches!(
+        file_name,
+        "Makefile" | "makefile" | "GNUmakefile" | "justfile" | "Justfile" | ".justfile"
+    ) {
+        Some(VersionSourceKind::BuildScript)
+    } else {
+        None
+    }
+}
+
+/// Find every toolchain version pinned in `content`. Comment lines are
+/// skipped, and each tool/version pair is reported once, at its first line.
+#[must_use]
+pub fn extract_text_signals(
+    content: &str,
+    path: &Path,
+    kind: VersionSourceKind,
+    patterns: &[TextSignalPattern],
+) -> Vec<TextSignal> {
+    let mut signals: Vec<TextSignal> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        for pattern in patterns {
+            for (position, _) in line.match_indices(pattern.marker) {
+                if !is_whole_key(line, position, pattern.marker) {
+                    continue;
+                }
+                let Some(version) = line
+                    .get(position.saturating_add(pattern.marker.len())..)
+                    .and_then(version_after_marker)
+                else {
+                    continue;
+                };
+
+                if signals
+                    .iter()
+                    .any(|signal| signal.tool == pattern.tool && signal.version == version)
+                {
+                    continue;
+                }
+                signals.push(TextSignal {
+                    tool: pattern.tool,
+                    version: version.to_string(),
+                    kind,
+                    path: path.to_path_buf(),
+                    line: index.saturating_add(1),
+                });
+            }
+        }
+    }
+
+    signals
+}
+
+/// Whether `marker`, found at byte `position` of `line`, is a whole key rather
+/// than part of a longer one.
+fn is_whole_key(line: &str, position: usize, marker: &str) -> bool {
+    if marker.starts_with(is_key_character)
+        && line
+            .get(..position)
+            .and_then(|before| before.chars().next_back())
+            .is_some_and(is_key_character)
+    {
+        return false;
+    }
+    if !marker.ends_with(is_key_character) {
+        return true;
+    }
+
+    let after = line
+        .get(position.saturating_add(marker.len())..)
+        .unwrap_or_default();
+    after
+        .trim_start_matches([' ', '\t', '"', '\''])
+        .starts_with(['=', ':'])
+        || (marker.starts_with("--") && after.starts_with([' ', '\t']))
+}
+
+const fn is_key_character(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_' || character == '-'
+}
+
+/// Read a dotted version such as `1.18.17` from the start of `rest`.
+fn version_after_marker(rest: &str) -> Option<&str> {
+    let rest = rest.trim_start_matches([' ', '\t', '=', ':', '"', '\'']);
+    let rest = rest.strip_prefix('v').unwrap_or(rest);
+    let end = rest
+        .find(|character: char| !character.is_ascii_digit() && character != '.')
+        .unwrap_or(rest.len());
+    let version = rest.get(..end)?.trim_end_matches('.');
+
+    (version.starts_with(|character: char| character.is_ascii_digit()) && version.contains('.'))
+        .then_some(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pins(content: &str) -> Vec<(PinnedTool, String, usize)> {
+        extract_text_signals(
+            content,
+            Path::new("ci.yml"),
+            VersionSourceKind::CiWorkflow,
+            text_signal_patterns(),
+        )
+        .into_iter()
+        .map(|signal| (signal.tool, signal.version, signal.line))
+        .collect()
+    }
+
+    #[test]
+    fn test_extracts_pins_from_workflow_steps() {
+        let workflow = r#"
+env:
+  SOLANA_VERSION: 1.18.17
+  ANCHOR_VERSION: ${{ inputs.anchor }}
+jobs:
+  build:
+    steps:
+      - uses: dtolnay/rust-toolchain@1.76.0
+      - uses: dtolnay/rust-toolchain@stable
+      # - run: avm install 0.29.0
+      - run: sh -c "$(curl -sSfL https://release.solana.com/v1.18.17/install)"
+      - run: cargo install --git https://github.com/coral-xyz/anchor --tag v0.30.1 anchor-cli
+      - run: cargo build-sbf --tools-version v1.41
+"#;
+
+        assert_eq!(
+            pins(workflow),
+            vec![
+                (PinnedTool::Solana, "1.18.17".to_string(), 3),
+                (PinnedTool::Rust, "1.76.0".to_string(), 8),
+                (PinnedTool::Anchor, "0.30.1".to_string(), 12),
+                (PinnedTool::PlatformTools, "1.41".to_string(), 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extracts_pins_from_dockerfile_and_makefile() {
+        assert_eq!(
+            pins("FROM rust:1.79.0-slim\nARG AGAVE_VERSION=2.1.0\nRUN avm use 0.31.0\n"),
+            vec![
+                (PinnedTool::Rust, "1.79.0".to_string(), 1),
+                (PinnedTool::Solana, "2.1.0".to_string(), 2),
+                (PinnedTool::Anchor, "0.31.0".to_string(), 3),
+            ]
+        );
+        assert_eq!(
+            text_signal_kind(Path::new(".github/workflows/ci.yaml")),
+            Some(VersionSourceKind::CiWorkflow)
+        );
+        assert_eq!(
+            text_signal_kind(Path::new("docker/build.Dockerfile")),
+            Some(VersionSourceKind::Dockerfile)
+        );
+        assert_eq!(
+            text_signal_kind(Path::new("justfile")),
+            Some(VersionSourceKind::BuildScript)
+        );
+        assert_eq!(text_signal_kind(Path::new("ci.yml")), None);
+    }
+
+    #[test]
+    fn test_ignores_markers_inside_longer_keys() {
+        let workflow = r"
+env:
+  MIN_RUST_VERSION: 1.70.0
+  RUST_VERSION_FILE: 1.71.0
+  SOLANA_VERSION_MAJOR=1.16
+  node-toolchain: 20.11.0
+  my_anchor_version: 0.28.0
+steps:
+  - run: echo $RUST_VERSION 1.72.0
+";
+
+        assert_eq!(pins(workflow), vec![]);
+    }
+
+    #[test]
+    fn test_matches_whole_keys_after_separators() {
+        assert_eq!(
+            pins(
+                "ENV RUST_VERSION=1.79.0\n  \"SOLANA_VERSION\": \"1.18.17\"\n  toolchain: 1.76.0\n"
+            ),
+            vec![
+                (PinnedTool::Rust, "1.79.0".to_string(), 1),
+                (PinnedTool::Solana, "1.18.17".to_string(), 2),
+                (PinnedTool::Rust, "1.76.0".to_string(), 3),
+            ]
+        );
+    }
+}
@@ -1,10 +1,41 @@
+use semver::VersionReq;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ProjectVersions {
+    /// The host toolchain, used for tests, the IDL build and the Anchor CLI.
     pub rust_version: Option<String>,
     pub solana_version: Option<String>,
     pub anchor_version: Option<String>,
+    /// The platform-tools release `cargo build-sbf` compiles programs with,
+    /// e.g. `v1.41`. Its bundled rustc is independent of `rust_version`.
+    pub platform_tools_version: Option<String>,
+    /// The full requirements behind the display strings above, when the
+    /// source declared one.
+    pub requirements: VersionRequirements,
+}
+
+/// Parsed version requirements for each field, e.g. `>=1.18, <2` for a
+/// `solana-program = ">=1.18,<2"` dependency whose display version is `1.18`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionRequirements {
+    pub rust: Option<VersionReq>,
+    pub solana: Option<VersionReq>,
+    pub anchor: Option<VersionReq>,
+}
+
+impl VersionRequirements {
+    #[must_use]
+    pub const fn get(&self, field: VersionField) -> Option<&VersionReq> {
+        match field {
+            VersionField::Rust => self.rust.as_ref(),
+            VersionField::Solana => self.solana.as_ref(),
+            VersionField::Anchor => self.anchor.as_ref(),
+        }
+    }
 }
 
 impl ProjectVersions {
@@ -15,14 +46,20 @@ impl ProjectVersions {
             || self.anchor_version.is_none()
     }
 
+    /// Whether any Solana or Anchor version was found. A requirement without a
+    /// display version, such as the Anchor range implied by an IDL, counts.
     #[must_use]
     pub const fn is_solana_project(&self) -> bool {
-        self.solana_version.is_some() || self.anchor_version.is_some()
+        self.solana_version.is_some()
+            || self.anchor_version.is_some()
+            || self.requirements.solana.is_some()
+            || self.requirements.anchor.is_some()
     }
 
     pub fn merge_missing_from(&mut self, other: &Self) {
         if self.rust_version.is_none() {
             self.rust_version.clone_from(&other.rust_version);
+            self.requirements.rust.clone_from(&other.requirements.rust);
         }
 
         if self.solana_version.is_none()
@@ -32,10 +69,26 @@ impl ProjectVersions {
                 .is_some_and(|version| version != "*")
         {
             self.solana_version.clone_from(&other.solana_version);
+            self.requirements
+                .solana
+                .clone_from(&other.requirements.solana);
         }
 
-        if self.anchor_version.is_none() {
+        if self.anchor_version.is_none()
+            && other
+                .anchor_version
+                .as_ref()
+                .is_some_and(|version| version != "*")
+        {
             self.anchor_version.clone_from(&other.anchor_version);
+            self.requirements
+                .anchor
+                .clone_from(&other.requirements.anchor);
+        }
+
+        if self.platform_tools_version.is_none() {
+            self.platform_tools_version
+                .clone_from(&other.platform_tools_version);
         }
     }
 }
@@ -47,11 +100,492 @@ pub enum VersionField {
     Anchor,
 }
 
+/// The layout of an Anchor IDL file, which reveals the Anchor release line
+/// that generated it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdlFormat {
+    /// The pre-0.30 layout with a top-level `version`.
+    Legacy,
+    /// The 0.30+ layout, carrying the IDL spec version from `metadata.spec`.
+    Spec(String),
+}
+
+impl IdlFormat {
+    /// The Anchor versions that produce this format.
+    #[must_use]
+    pub fn anchor_requirement(&self) -> VersionReq {
+        let comparator = match self {
+            Self::Legacy => "<0.30.0",
+            Self::Spec(_) => ">=0.30.0",
+        };
+        VersionReq::parse(comparator).unwrap_or(VersionReq::STAR)
+    }
+}
+
+/// A tool whose version can be pinned in CI workflows and build scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinnedTool {
+    Rust,
+    Solana,
+    Anchor,
+    /// The platform-tools release passed to `cargo build-sbf --tools-version`.
+    PlatformTools,
+}
+
+impl PinnedTool {
+    /// The project version this tool's pins describe. Platform-tools pins do
+    /// not map to any of them.
+    #[must_use]
+    pub const fn field(self) -> Option<VersionField> {
+        match self {
+            Self::Rust => Some(VersionField::Rust),
+            Self::Solana => Some(VersionField::Solana),
+            Self::Anchor => Some(VersionField::Anchor),
+            Self::PlatformTools => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::Solana => "Solana",
+            Self::Anchor => "Anchor",
+            Self::PlatformTools => "platform-tools",
+        }
+    }
+}
+
+/// Text that precedes a version of `tool`, e.g. `dtolnay/rust-toolchain@`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSignalPattern {
+    pub tool: PinnedTool,
+    pub marker: &'static str,
+}
+
+/// A version pin found in a CI workflow, Dockerfile or build script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSignal {
+    pub tool: PinnedTool,
+    pub version: String,
+    pub kind: VersionSourceKind,
+    pub path: PathBuf,
+    /// 1-based line number of the pin.
+    pub line: usize,
+}
+
+/// A crate version recovered from a build path embedded in a program binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryCrateVersion {
+    pub name: String,
+    pub version: String,
+}
+
+/// What a compiled SBF program reveals about the toolchain that built it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramBinary {
+    /// The SBF compiler from the `.comment` section, e.g. `1.75.0-dev`.
+    pub rustc_version: Option<String>,
+    /// The platform-tools release, e.g. `v1.41`, when the binary names it.
+    pub platform_tools_version: Option<String>,
+    /// Every entry of the `.comment` section.
+    pub compiler_comments: Vec<String>,
+    /// Recognised crates found in embedded source paths, sorted by name.
+    pub crate_versions: Vec<BinaryCrateVersion>,
+}
+
+/// An npm package from a project's TypeScript client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsPackage {
+    pub name: String,
+    /// The range declared in `package.json`, e.g. `^0.30.1`.
+    pub requirement: String,
+    /// The version installed according to the lockfile, when there is one.
+    pub resolved: Option<String>,
+}
+
+impl TsPackage {
+    /// The resolved version, or the lower bound of the declared range.
+    #[must_use]
+    pub fn version(&self) -> String {
+        self.resolved.clone().unwrap_or_else(|| {
+            self.requirement
+                .trim_start_matches(['^', '~', '=', '>', '<', 'v'])
+                .trim()
+                .to_string()
+        })
+    }
+}
+
+/// Anchor and web3.js versions used by a project's TypeScript client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsClientVersions {
+    pub package_json: PathBuf,
+    pub lockfile: Option<PathBuf>,
+    /// `@coral-xyz/anchor`, or the older `@project-serum/anchor`.
+    pub anchor: Option<TsPackage>,
+    /// `@solana/web3.js`.
+    pub web3: Option<TsPackage>,
+}
+
+/// Release channel named by a `rust-toolchain` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolchainChannel {
+    Stable,
+    /// `beta`, optionally pinned to a dated build such as `beta-2024-01-04`.
+    Beta {
+        date: Option<String>,
+    },
+    /// `nightly`, optionally pinned to a dated build such as
+    /// `nightly-2023-10-29`.
+    Nightly {
+        date: Option<String>,
+    },
+    /// An explicit release such as `1.76.0` or `1.76`.
+    Version(String),
+    /// Any other name, e.g. a toolchain registered with `rustup toolchain link`.
+    Custom(String),
+}
+
+impl ToolchainChannel {
+    /// The channel name as rustup spells it, without a host triple.
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            Self::Stable => "stable".to_string(),
+            Self::Beta { date: None } => "beta".to_string(),
+            Self::Beta { date: Some(date) } => format!("beta-{date}"),
+            Self::Nightly { date: None } => "nightly".to_string(),
+            Self::Nightly { date: Some(date) } => format!("nightly-{date}"),
+            Self::Version(version) | Self::Custom(version) => version.clone(),
+        }
+    }
+}
+
+/// Everything a `rust-toolchain` file pins, not just its channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RustToolchainSpec {
+    /// `None` when the file only names a local toolchain `path`.
+    pub channel: Option<ToolchainChannel>,
+    /// Host triple suffix, e.g. `x86_64-unknown-linux-gnu` in
+    /// `stable-x86_64-unknown-linux-gnu`.
+    pub host: Option<String>,
+    pub components: Vec<String>,
+    pub targets: Vec<String>,
+    pub profile: Option<String>,
+    pub path: Option<PathBuf>,
+}
+
+impl RustToolchainSpec {
+    /// The full toolchain name to pass to rustup, including the host triple.
+    #[must_use]
+    pub fn toolchain_name(&self) -> Option<String> {
+        let channel = self.channel.as_ref()?.name();
+        Some(match &self.host {
+            Some(host) => format!("{channel}-{host}"),
+            None => channel,
+        })
+    }
+}
+
+/// A parsed `Anchor.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorConfig {
+    pub toolchain: AnchorToolchain,
+    pub features: AnchorFeatures,
+    pub workspace: AnchorWorkspace,
+    /// Deployed programs keyed by cluster (`localnet`, `devnet`, ...), then by
+    /// program name.
+    pub programs: BTreeMap<String, BTreeMap<String, AnchorProgram>>,
+    pub provider: AnchorProvider,
+    pub scripts: BTreeMap<String, String>,
+    pub test: Option<AnchorTestConfig>,
+}
+
+/// The `[toolchain]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorToolchain {
+    pub anchor_version: Option<String>,
+    pub solana_version: Option<String>,
+    /// `npm`, `yarn` or `pnpm`.
+    pub package_manager: Option<String>,
+}
+
+/// The `[features]` section.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnchorFeatures {
+    pub resolution: Option<bool>,
+    pub skip_lint: Option<bool>,
+}
+
+/// The `[workspace]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorWorkspace {
+    pub members: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// A program entry under `[programs.<cluster>]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchorProgram {
+    pub address: String,
+    pub idl: Option<String>,
+}
+
+/// The `[provider]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorProvider {
+    pub cluster: Option<String>,
+    pub wallet: Option<String>,
+}
+
+/// The `[test]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorTestConfig {
+    pub startup_wait: Option<u64>,
+    pub shutdown_wait: Option<u64>,
+    pub upgradeable: Option<bool>,
+    pub genesis: Vec<AnchorGenesisProgram>,
+    pub validator: Option<AnchorValidatorConfig>,
+}
+
+/// A `[[test.genesis]]` program loaded into the test validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchorGenesisProgram {
+    pub address: String,
+    pub program: String,
+    pub upgradeable: Option<bool>,
+}
+
+/// The `[test.validator]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorValidatorConfig {
+    pub url: Option<String>,
+    pub bind_address: Option<String>,
+    pub ledger: Option<String>,
+    pub rpc_port: Option<u16>,
+    /// Addresses from `[[test.validator.clone]]`.
+    pub clone: Vec<String>,
+    pub account: Vec<AnchorValidatorAccount>,
+}
+
+/// A `[[test.validator.account]]` entry loaded from a JSON file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchorValidatorAccount {
+    pub address: String,
+    pub filename: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VersionSourceKind {
     RustToolchain,
     AnchorToml,
+    /// A `.anchorversion` file read by avm.
+    AnchorVersionFile,
+    /// An asdf or mise `.tool-versions` file.
+    ToolVersions,
+    /// The `[tools]` table of `mise.toml` or `.mise.toml`.
+    MiseToml,
+    CargoLock,
     CargoToml,
+    /// `rust-version` from `[package]` or `[workspace.package]`.
+    CargoRustVersion,
+    /// An Anchor IDL under `target/idl/` or `idl/`.
+    Idl,
+    /// A compiled program such as `target/deploy/*.so`.
+    ProgramBinary,
+    /// A GitHub Actions workflow or `.gitlab-ci.yml`.
+    CiWorkflow,
+    /// A `Dockerfile`.
+    Dockerfile,
+    /// A Makefile or justfile.
+    BuildScript,
+}
+
+impl VersionSourceKind {
+    /// Relative trust of a source kind when several sources in the same
+    /// directory report the same field. Higher values win.
+    ///
+    /// Explicit toolchain pins beat the lockfile, and the lockfile beats the
+    /// lower bound taken from a `Cargo.toml` requirement. `Anchor.toml` wins
+    /// over `.anchorversion` and asdf/mise pins because `anchor` itself
+    /// switches to the `[toolchain]` version, whereas avm only consults the
+    /// file when no version is configured. A compiled program records exactly
+    /// what was linked, so it ranks with the lockfile. IDLs and pins found in
+    /// CI or build scripts only fill gaps left by the project files.
+    #[must_use]
+    pub const fn precedence(self) -> u8 {
+        match self {
+            Self::RustToolchain | Self::AnchorToml => 4,
+            Self::AnchorVersionFile | Self::ToolVersions | Self::MiseToml => 3,
+            Self::CargoLock | Self::ProgramBinary => 2,
+            Self::CargoToml | Self::CargoRustVersion => 1,
+            Self::Idl | Self::CiWorkflow | Self::Dockerfile | Self::BuildScript => 0,
+        }
+    }
+}
+
+/// A crate whose version is treated as a version signal for `field`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecognizedCrate {
+    /// Package name, or a name prefix when it ends in `*` (e.g. `agave-*`).
+    pub name: &'static str,
+    pub field: VersionField,
+    /// Rank among crates reporting the same field. Higher wins.
+    pub weight: u8,
+}
+
+impl RecognizedCrate {
+    #[must_use]
+    pub fn matches(&self, package: &str) -> bool {
+        self.name
+            .strip_suffix('*')
+            .map_or(self.name == package, |prefix| package.starts_with(prefix))
+    }
+}
+
+/// A version read from a recognised dependency in a manifest or lockfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencySignal {
+    pub field: VersionField,
+    pub weight: u8,
+    /// Display version: the lower bound of the requirement.
+    pub version: String,
+    pub requirement: Option<VersionReq>,
+    pub origin: DependencyOrigin,
+}
+
+/// The dependency table of a `Cargo.toml` an entry was declared in.
+///
+/// Target-specific tables such as `[target.'cfg(..)'.dependencies]` use the
+/// same variants, with the target recorded separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyTable {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+    /// `[workspace.dependencies]`
+    Workspace,
+}
+
+impl DependencyTable {
+    /// Rank of the table when several report the same field. Higher wins.
+    ///
+    /// Dev and build dependencies do not end up in the deployed program, so
+    /// they only count when no normal dependency reports the field.
+    #[must_use]
+    pub const fn precedence(self) -> u8 {
+        match self {
+            Self::Normal | Self::Workspace => 1,
+            Self::Dev | Self::Build => 0,
+        }
+    }
+}
+
+/// Whether a git repository is the canonical home of a project or a fork.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepositoryKind {
+    Upstream,
+    Fork,
+}
+
+/// A git repository recognised as the source of Anchor or Solana crates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownRepository {
+    /// `owner/name` as it appears in the repository URL.
+    pub slug: &'static str,
+    pub kind: RepositoryKind,
+}
+
+/// The git source of a dependency declared with `git = "..."`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    pub url: String,
+    /// `owner/name` taken from the URL, e.g. `coral-xyz/anchor`.
+    pub repository: Option<String>,
+    /// Whether the repository is a known upstream or fork. `None` when the
+    /// repository is not recognised.
+    pub repository_kind: Option<RepositoryKind>,
+    pub tag: Option<String>,
+    pub rev: Option<String>,
+    pub branch: Option<String>,
+}
+
+impl GitSource {
+    /// The release version named by the tag, e.g. `0.29.0` for `v0.29.0`.
+    #[must_use]
+    pub fn tag_version(&self) -> Option<String> {
+        let tag = self.tag.as_deref()?;
+        semver::Version::parse(tag.trim_start_matches('v'))
+            .ok()
+            .map(|version| version.to_string())
+    }
+}
+
+/// A dependency entry read from a `Cargo.toml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestDependency {
+    /// The effective package name: the `package` key when the dependency is
+    /// renamed, otherwise the table key.
+    pub name: String,
+    /// The table key when it differs from the package name, e.g. `anchor`
+    /// for `anchor = { package = "anchor-lang", ... }`.
+    pub alias: Option<String>,
+    pub table: DependencyTable,
+    /// The `cfg(..)` expression or target triple for target-specific tables.
+    pub target: Option<String>,
+    /// The raw version requirement, if the entry declares one.
+    pub requirement: Option<String>,
+    /// Whether the entry is inherited with `workspace = true`.
+    pub workspace: bool,
+    pub git: Option<GitSource>,
+}
+
+impl ManifestDependency {
+    /// The key the dependency is declared under in its table.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoManifest {
+    pub dependencies: Vec<ManifestDependency>,
+    /// Whether the manifest has a `[workspace]` table.
+    pub is_workspace_root: bool,
+    /// `package.rust-version`, the minimum supported Rust version.
+    pub rust_version: Option<String>,
+    /// Whether `package.rust-version` is `{ workspace = true }`.
+    pub inherits_rust_version: bool,
+    /// `workspace.package.rust-version` on a workspace root.
+    pub workspace_rust_version: Option<String>,
+    /// `workspace.members` on a workspace root. Entries may be globs such as
+    /// `programs/*`.
+    pub workspace_members: Vec<String>,
+    /// `workspace.exclude` on a workspace root.
+    pub workspace_exclude: Vec<String>,
+}
+
+/// The dependency a manifest or lockfile signal was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyOrigin {
+    pub crate_name: String,
+    /// The table key the crate was renamed to with `package = "..."`.
+    pub alias: Option<String>,
+    /// The manifest table the dependency was declared in. `None` for
+    /// lockfile signals.
+    pub table: Option<DependencyTable>,
+    /// The `cfg(..)` expression or target triple for target-specific tables.
+    pub target: Option<String>,
+    /// The workspace root manifest the version was inherited from, for
+    /// entries declared with `workspace = true`.
+    pub inherited_from: Option<PathBuf>,
+    /// The repository, tag and rev for git dependencies.
+    pub git: Option<GitSource>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -60,13 +594,92 @@ pub struct VersionSource {
     pub kind: VersionSourceKind,
     pub path: PathBuf,
     pub value: String,
+    /// The full requirement `value` was taken from, when the source declared one.
+    pub requirement: Option<VersionReq>,
+    pub dependency: Option<DependencyOrigin>,
+}
+
+/// Version signals and warnings gathered from a single directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryScan {
+    pub versions: ProjectVersions,
+    pub rust_toolchain: Option<RustToolchainSpec>,
+    pub anchor_config: Option<AnchorConfig>,
+    pub ts_client: Option<TsClientVersions>,
+    /// Compiled programs inspected, keyed by path.
+    pub program_binaries: BTreeMap<PathBuf, ProgramBinary>,
+    pub text_signals: Vec<TextSignal>,
+    pub sources: Vec<VersionSource>,
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompatibilityReason {
     ExactAnchorMatch,
     ExactSolanaMatch,
+    /// No rule matched, and the rule nearest the detected version was chosen
+    /// under [`FallbackPolicy::NearestLower`] or
+    /// [`FallbackPolicy::NearestHigher`].
+    FallbackNearestKnown,
     FallbackLatestKnown,
+    /// Every rule matched by Solana or Anchor disagrees with another detected
+    /// version. The Solana match is kept, and each candidate's disagreements
+    /// are listed in [`CompatibilityAssessment::candidates`].
+    Conflict,
+}
+
+/// A rule matched by the detected Solana or Anchor version, checked against
+/// the other detected versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateMatch {
+    /// [`CompatibilityReason::ExactSolanaMatch`] or
+    /// [`CompatibilityReason::ExactAnchorMatch`], depending on which version
+    /// matched the rule.
+    pub reason: CompatibilityReason,
+    pub rule: CompatibilityRule,
+    pub match_kind: MatchKind,
+    /// The detected versions that disagree with `rule`.
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+/// A detected version that disagrees with a matched compatibility rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    pub field: VersionField,
+    pub detected: String,
+    /// The rule's recommended version for `field`.
+    pub expected: String,
+    pub rule: CompatibilityRule,
+}
+
+/// What to recommend when no compatibility rule matches the detected
+/// versions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FallbackPolicy {
+    /// Suggest the newest rule in the matrix, with low confidence.
+    #[default]
+    LatestKnown,
+    /// Suggest the closest rule below the detected version, falling back to
+    /// the newest rule when there is none.
+    NearestLower,
+    /// Suggest the closest rule above the detected version, falling back to
+    /// the newest rule when there is none.
+    NearestHigher,
+    /// Fail instead of guessing.
+    Strict,
+}
+
+/// How far the rule chosen by a nearest-neighbour fallback is from the
+/// detected version, as the difference in each version component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionDistance {
+    /// The version the distance was measured for: Solana when it was
+    /// detected, otherwise Anchor.
+    pub field: VersionField,
+    pub requested: String,
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -83,22 +696,250 @@ pub struct InferredFields {
     pub anchor_version: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A known-good combination of Anchor, Solana and Rust.
+///
+/// `anchor`, `solana` and `rust` are the recommended versions, and `tested`
+/// holds the ranges the combination is known to work across. A point rule
+/// for a single release uses `=version` ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompatibilityRule {
-    pub solana: &'static str,
-    pub anchor: &'static str,
-    pub rust: &'static str,
-    pub notes: &'static str,
-    pub source: &'static str,
+    pub solana: String,
+    pub anchor: String,
+    /// Version of the `@coral-xyz/anchor` TypeScript client released with
+    /// `anchor`.
+    pub ts_client: String,
+    /// The host Rust toolchain for tests, the IDL build and the Anchor CLI.
+    /// Programs themselves are compiled with the rustc bundled in
+    /// platform-tools; see `platform_tools_for_solana`.
+    pub rust: String,
+    pub tested: TestedRanges,
+    pub notes: String,
+    pub source: String,
+}
+
+impl CompatibilityRule {
+    /// The recommended version for `field`.
+    #[must_use]
+    pub fn version(&self, field: VersionField) -> &str {
+        match field {
+            VersionField::Rust => &self.rust,
+            VersionField::Solana => &self.solana,
+            VersionField::Anchor => &self.anchor,
+        }
+    }
+}
+
+/// The version ranges a compatibility rule was tested with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestedRanges {
+    pub anchor: VersionReq,
+    pub solana: VersionReq,
+    pub rust: VersionReq,
+}
+
+impl TestedRanges {
+    #[must_use]
+    pub const fn get(&self, field: VersionField) -> &VersionReq {
+        match field {
+            VersionField::Rust => &self.rust,
+            VersionField::Solana => &self.solana,
+            VersionField::Anchor => &self.anchor,
+        }
+    }
+}
+
+/// The span of versions a rule supports for one field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionBounds {
+    /// The oldest supported version, or `None` when the tested range has no
+    /// lower bound.
+    pub minimum: Option<String>,
+    pub recommended: String,
+    /// The newest version the rule was tested with, or `None` when the tested
+    /// range is open or ends in an exclusive bound such as `<2.2`.
+    pub max_tested: Option<String>,
+}
+
+/// Where a detected version falls relative to a rule's bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsStatus {
+    /// The version is the rule's recommended version.
+    Recommended,
+    /// The version is inside the rule's tested range.
+    WithinBounds,
+    BelowMinimum,
+    /// The version is newer than any the rule was tested with. It may still
+    /// work, for example as a later patch release.
+    AboveMaxTested,
+}
+
+/// A detected version checked against a rule's bounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionCheck {
+    pub field: VersionField,
+    pub detected: String,
+    /// The version compared, e.g. the stable equivalent of a nightly
+    /// toolchain or the lower bound of a requirement.
+    pub compared: String,
+    pub bounds: VersionBounds,
+    pub status: BoundsStatus,
+}
+
+/// The result of checking a project's pinned versions against the rule for
+/// its Anchor release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub rule: CompatibilityRule,
+    pub checks: Vec<VersionCheck>,
+}
+
+impl Verification {
+    /// Whether every checked version is inside the rule's tested range.
+    #[must_use]
+    pub fn within_bounds(&self) -> bool {
+        self.checks.iter().all(|check| {
+            matches!(
+                check.status,
+                BoundsStatus::Recommended | BoundsStatus::WithinBounds
+            )
+        })
+    }
+}
+
+/// An ordered set of compatibility rules, newest Anchor first.
+///
+/// The built-in matrix is available from `CompatibilityMatrix::built_in`.
+/// Others are loaded from TOML or JSON files and can be merged into it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityMatrix {
+    pub rules: Vec<CompatibilityRule>,
+}
+
+/// How a version lookup matched a compatibility rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// The version is exactly the rule's version.
+    Exact,
+    /// The version is not the rule's recommended version but falls within
+    /// its tested range.
+    InRange,
+    /// The version is a later patch (or a pre-release) of the rule's
+    /// `major.minor` release and inherits its compatibility.
+    PatchInherited,
+    /// The input was a partial version or a requirement, and the rule is the
+    /// newest one satisfying it.
+    Range,
+    /// No rule covers the version, and this is the nearest one under the
+    /// fallback policy.
+    Nearest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleMatch<'a> {
+    pub rule: &'a CompatibilityRule,
+    pub kind: MatchKind,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompatibilityAssessment {
-    pub matched_rule: Option<&'static CompatibilityRule>,
-    pub latest_rule: &'static CompatibilityRule,
+    pub matched_rule: Option<CompatibilityRule>,
+    pub match_kind: Option<MatchKind>,
+    pub latest_rule: CompatibilityRule,
     pub reason: CompatibilityReason,
     pub confidence: Confidence,
+    /// Set when the rule was chosen as the nearest neighbour of an unmatched
+    /// version.
+    pub fallback_distance: Option<VersionDistance>,
+    /// The rules matched by the detected Solana and Anchor versions, in that
+    /// order, each with the detected versions that disagree with it. Empty
+    /// when the rule was chosen by a fallback.
+    pub candidates: Vec<CandidateMatch>,
     pub inferred_fields: InferredFields,
+    /// Set when the detected Rust toolchain is a dated nightly or beta that
+    /// was compared as its stable equivalent.
+    pub rust_mapping: Option<RustVersionMapping>,
+    /// The SBF toolchain used by `cargo build-sbf`: the detected
+    /// platform-tools pin, or the release bundled with the Solana version.
+    pub sbf_toolchain: Option<&'static PlatformToolsRelease>,
+}
+
+/// The platform-tools release a Solana/Agave release line installs for
+/// `cargo build-sbf`, and the compiler it bundles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlatformToolsRelease {
+    /// The Solana/Agave `major.minor` release line, e.g. `1.18`.
+    pub solana: &'static str,
+    /// The platform-tools version, e.g. `v1.41`.
+    pub platform_tools: &'static str,
+    pub rustc: &'static str,
+    pub cargo: &'static str,
+}
+
+/// A stable Rust release and the date it shipped (`YYYY-MM-DD`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RustRelease {
+    pub version: &'static str,
+    pub date: &'static str,
+}
+
+/// A nightly or beta toolchain and the stable release it corresponds to,
+/// e.g. `nightly-2023-10-29` and `1.75.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustVersionMapping {
+    pub toolchain: String,
+    pub stable_equivalent: String,
+}
+
+/// A version requirement declared by one project file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+    pub field: VersionField,
+    pub requirement: VersionReq,
+    pub kind: VersionSourceKind,
+    pub path: PathBuf,
+}
+
+/// A compatibility rule whose (anchor, solana, rust) triple satisfies every
+/// constraint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeasibleCombination {
+    pub rule: CompatibilityRule,
+    /// How many constraints the rule satisfies directly rather than through
+    /// patch inheritance.
+    pub exact_matches: usize,
+}
+
+/// A minimal set of constraints that no compatibility rule satisfies
+/// together. Dropping any one of them makes the rest satisfiable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsatisfiableCore {
+    pub constraints: Vec<VersionConstraint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstraintSolution {
+    /// Feasible combinations, best first.
+    Feasible(Vec<FeasibleCombination>),
+    Unsatisfiable(UnsatisfiableCore),
+}
+
+impl fmt::Display for UnsatisfiableCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No known compatibility rule satisfies all of: ")?;
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(
+                f,
+                "{:?} {} (from {})",
+                constraint.field,
+                constraint.requirement,
+                constraint.path.display()
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -106,10 +947,47 @@ pub struct DetectionReport {
     pub detected: ProjectVersions,
     pub resolved: ProjectVersions,
     pub compatibility: CompatibilityAssessment,
+    /// The first `rust-toolchain` file found while scanning.
+    pub rust_toolchain: Option<RustToolchainSpec>,
+    /// The first `Anchor.toml` found while scanning.
+    pub anchor_config: Option<AnchorConfig>,
+    /// The first `package.json` found that uses Anchor or web3.js.
+    pub ts_client: Option<TsClientVersions>,
+    /// Compiled programs inspected, keyed by path. Empty unless detection ran
+    /// on program binaries.
+    pub program_binaries: BTreeMap<PathBuf, ProgramBinary>,
+    /// Toolchain pins found in CI workflows, Dockerfiles and build scripts.
+    pub text_signals: Vec<TextSignal>,
     pub sources: Vec<VersionSource>,
+    /// The manifest and lockfile requirements, plus the pins that supplied a
+    /// detected version, intersected with the compatibility matrix.
+    pub solution: ConstraintSolution,
     pub warnings: Vec<String>,
 }
 
+impl DetectionReport {
+    /// Return the highest-precedence source that supplied the detected value
+    /// for `field`.
+    #[must_use]
+    pub fn source_for(&self, field: VersionField) -> Option<&VersionSource> {
+        let detected = match field {
+            VersionField::Rust => self.detected.rust_version.as_deref(),
+            VersionField::Solana => self.detected.solana_version.as_deref(),
+            VersionField::Anchor => self.detected.anchor_version.as_deref(),
+        }?;
+
+        self.sources
+            .iter()
+            .filter(|source| source.field == field && source.value == detected)
+            .fold(None, |best: Option<&VersionSource>, source| match best {
+                Some(current) if current.kind.precedence() >= source.kind.precedence() => {
+                    Some(current)
+                }
+                _ => Some(source),
+            })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CurrentEnvironment {
     pub rust_version: Option<String>,
@@ -121,6 +999,13 @@ pub struct CurrentEnvironment {
 pub struct ScanOptions {
     pub recursive: bool,
     pub skip_directories: &'static [&'static str],
+    pub recognized_crates: &'static [RecognizedCrate],
+    pub text_signal_patterns: &'static [TextSignalPattern],
+    /// The compatibility matrix detected versions are resolved against. A
+    /// custom matrix is passed as `Cow::Owned`.
+    pub matrix: Cow<'static, CompatibilityMatrix>,
+    /// What to recommend when no rule matches the detected versions.
+    pub fallback_policy: FallbackPolicy,
 }
 
 impl Default for ScanOptions {
@@ -137,6 +1022,10 @@ impl Default for ScanOptions {
                 ".vscode",
                 "coverage",
             ],
+            recognized_crates: crate::crates::recognized_crates(),
+            text_signal_patterns: crate::text_signals::text_signal_patterns(),
+            matrix: Cow::Borrowed(CompatibilityMatrix::built_in()),
+            fallback_policy: FallbackPolicy::LatestKnown,
         }
     }
 }
@@ -0,0 +1,229 @@
+use anyhow::{Result, anyhow};
+use semver::Version;
+
+use crate::parse::{
+    exact_version_requirement, parse_rust_toolchain_spec, requirement_lower_bound,
+    requirement_upper_bound,
+};
+use crate::rust_releases::stable_equivalent;
+use crate::types::{
+    BoundsStatus, CompatibilityMatrix, CompatibilityRule, ProjectVersions, Verification,
+    VersionBounds, VersionCheck, VersionField,
+};
+
+/// Check the detected Solana and Rust versions against the built-in rule for
+/// the detected Anchor release.
+///
+/// See [`CompatibilityMatrix::verify_versions`].
+///
+/// # Errors
+///
+/// Returns an error when no Anchor version was detected or no rule covers it.
+pub fn verify_versions(detected: &ProjectVersions) -> Result<Verification> {
+    CompatibilityMatrix::built_in().verify_versions(detected)
+}
+
+impl CompatibilityMatrix {
+    /// Check the detected Solana and Rust versions against the rule for the
+    /// detected Anchor release.
+    ///
+    /// Unlike [`CompatibilityMatrix::resolve_versions`], which suggests the
+    /// rule's recommended versions, this accepts any version inside the rule's
+    /// tested range and reports where each one falls. Rust toolchains are
+    /// compared as their stable equivalent, and requirements by their lower
+    /// bound.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when no Anchor version was detected or no rule covers
+    /// it.
+    pub fn verify_versions(&self, detected: &ProjectVersions) -> Result<Verification> {
+        let anchor_version = detected
+            .anchor_version
+            .as_ref()
+            .ok_or_else(|| anyhow!("No Anchor version detected to verify against."))?;
+        let matched = detected.requirements.anchor.as_ref().map_or_else(
+            || self.match_rule_by_anchor(anchor_version),
+            |requirement| self.match_rule_by_anchor_requirement(requirement),
+        );
+        let rule = matched
+            .ok_or_else(|| anyhow!("No compatibility rule covers Anchor {anchor_version}."))?
+            .rule;
+
+        let checks = [VersionField::Solana, VersionField::Rust]
+            .into_iter()
+            .filter_map(|field| {
+                let detected_version = match field {
+                    VersionField::Solana => detected.solana_version.as_ref(),
+                    VersionField::Rust => detected.rust_version.as_ref(),
+                    VersionField::Anchor => None,
+                }?;
+                let compared = comparable_version(detected, field, detected_version)?;
+                Some(rule.check(field, detected_version, &compared))
+            })
+            .collect();
+
+        Ok(Verification {
+            rule: rule.clone(),
+            checks,
+        })
+    }
+}
+
+impl CompatibilityRule {
+    /// The minimum, recommended and maximum tested versions for `field`, taken
+    /// from its tested range.
+    #[must_use]
+    pub fn bounds(&self, field: VersionField) -> VersionBounds {
+        let tested = self.tested.get(field);
+        VersionBounds {
+            minimum: requirement_lower_bound(tested).map(|version| version.to_string()),
+            recommended: self.version(field).to_string(),
+            max_tested: requirement_upper_bound(tested).map(|version| version.to_string()),
+        }
+    }
+
+    /// Check `version`, displayed as `detected`, against this rule's bounds
+    /// for `field`. Later patches of the recommended or maximum tested
+    /// release count as within bounds, as in [`CompatibilityRule::satisfies`].
+    #[must_use]
+    pub fn check(&self, field: VersionField, detected: &str, version: &Version) -> VersionCheck {
+        let bounds = self.bounds(field);
+        let status = if Version::parse(&bounds.recommended).is_ok_and(|recommended| {
+            recommended.cmp_precedence(version) == std::cmp::Ordering::Equal
+        }) {
+            BoundsStatus::Recommended
+        } else if exact_version_requirement(&version.to_string())
+            .is_some_and(|requirement| self.satisfies(field, &requirement))
+        {
+            BoundsStatus::WithinBounds
+        } else if bounds
+            .minimum
+            .as_deref()
+            .and_then(|minimum| Version::parse(minimum).ok())
+            .is_some_and(|minimum| *version < minimum)
+        {
+            BoundsStatus::BelowMinimum
+        } else {
+            BoundsStatus::AboveMaxTested
+        };
+
+        VersionCheck {
+            field,
+            detected: detected.to_string(),
+            compared: version.to_string(),
+            bounds,
+            status,
+        }
+    }
+}
+
+/// The concrete version to compare for a detected field.
+fn comparable_version(
+    detected: &ProjectVersions,
+    field: VersionField,
+    version: &str,
+) -> Option<Version> {
+    if field == VersionField::Rust && detected.requirements.rust.is_none() {
+        return stable_equivalent(parse_rust_toolchain_spec(version).ok()?.channel.as_ref()?);
+    }
+
+    detected
+        .requirements
+        .get(field)
+        .cloned()
+        .or_else(|| exact_version_requirement(version))
+        .as_ref()
+        .and_then(requirement_lower_bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_versions_are_checked_against_rule_bounds() {
+        let matrix = CompatibilityMatrix::from_toml_str(
+            r#"
+[[rules]]
+anchor = "0.31.1"
+solana = { min = "2.0.0", recommended = "2.1.0", max_tested = "2.1.21" }
+rust = { min = "1.79.0", recommended = "1.84.1" }
+"#,
+        )
+        .unwrap();
+        let detected = |solana: &str, rust: &str| ProjectVersions {
+            rust_version: Some(rust.to_string()),
+            solana_version: Some(solana.to_string()),
+            anchor_version: Some("0.31.1".to_string()),
+            ..ProjectVersions::default()
+        };
+
+        let verification = matrix
+            .verify_versions(&detected("2.1.14", "1.84.1"))
+            .unwrap();
+        assert!(verification.within_bounds());
+        let statuses: Vec<_> = verification
+            .checks
+            .iter()
+            .map(|check| (check.field, check.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (VersionField::Solana, BoundsStatus::WithinBounds),
+                (VersionField::Rust, BoundsStatus::Recommended),
+            ]
+        );
+        assert_eq!(
+            verification.checks[0].bounds,
+            VersionBounds {
+                minimum: Some("2.0.0".to_string()),
+                recommended: "2.1.0".to_string(),
+                max_tested: Some("2.1.21".to_string()),
+            }
+        );
+
+        let verification = matrix
+            .verify_versions(&detected("2.2.0", "nightly-2023-10-29"))
+            .unwrap();
+        assert!(!verification.within_bounds());
+        assert_eq!(verification.checks[0].status, BoundsStatus::AboveMaxTested);
+        assert_eq!(verification.checks[1].compared, "1.75.0");
+        assert_eq!(verification.checks[1].status, BoundsStatus::BelowMinimum);
+
+        let (resolved, _, _) = matrix
+            .resolve_versions(&ProjectVersions {
+                anchor_version: Some("0.31.1".to_string()),
+                ..ProjectVersions::default()
+            })
+            .unwrap();
+        assert_eq!(resolved.solana_version.as_deref(), Some("2.1.0"));
+        assert!(verify_versions(&ProjectVersions::default()).is_err());
+    }
+
+    #[test]
+    fn test_built_in_point_rules_accept_later_patches() {
+        let verification = verify_versions(&ProjectVersions {
+            rust_version: Some("1.76.0".to_string()),
+            solana_version: Some("1.18.26".to_string()),
+            anchor_version: Some("0.30.1".to_string()),
+            ..ProjectVersions::default()
+        })
+        .unwrap();
+        assert!(verification.within_bounds());
+        assert_eq!(verification.checks[0].status, BoundsStatus::WithinBounds);
+        assert_eq!(
+            verification.checks[0].bounds.max_tested.as_deref(),
+            Some("1.18.17")
+        );
+
+        let verification = verify_versions(&ProjectVersions {
+            solana_version: Some("1.18.26".to_string()),
+            anchor_version: Some("0.29.0".to_string()),
+            ..ProjectVersions::default()
+        })
+        .unwrap();
+        assert_eq!(verification.checks[0].status, BoundsStatus::AboveMaxTested);
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "anchor-version-detector-cli-{name}-{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_git_rev_only_anchor_project_is_detected() {
+    let root = scratch_dir("git-rev");
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"program\"\n\n[dependencies]\nanchor-lang = { git = \"https://github.com/coral-xyz/anchor\", rev = \"abc\" }\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_anchor-version-detector"))
+        .arg(&root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains(
+        "is pinned to rev abc of coral-xyz/anchor, which cannot be mapped to a release."
+    ));
+    assert!(stdout.contains(
+        "Warning: Anchor version could not be determined exactly. Suggesting latest known compatible version."
+    ));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_msrv_below_rule_does_not_become_host_toolchain() {
+    let root = scratch_dir("msrv");
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"program\"\nrust-version = \"1.70\"\n\n[dependencies]\nanchor-lang = \"0.30.1\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_anchor-version-detector"))
+        .arg(&root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Host Rust (tests, IDL build): 1.76.0 (MSRV 1.70 from"));
+    assert!(stdout.contains("rustup default 1.76.0\n"));
+    assert!(stdout.contains(
+        "Warning: Rust MSRV 1.70 is older than Rust 1.76.0 required by Anchor 0.30.1 and Solana 1.18.17."
+    ));
+
+    fs::remove_dir_all(&root).unwrap();
+}